@@ -2,13 +2,24 @@
 
 use frame_support::{
     pallet_prelude::MaxEncodedLen,
-    traits::{Currency, ExistenceRequirement::AllowDeath},
-    PalletId,
+    traits::{
+        tokens::nonfungibles::{
+            Inspect as NftInspect, Mutate as NftMutate, Transfer as NftTransfer,
+        },
+        Currency,
+        ExistenceRequirement::KeepAlive,
+        Get, Time,
+    },
+    BoundedVec, CloneNoBound, EqNoBound, PalletId, PartialEqNoBound, RuntimeDebugNoBound,
 };
 use scale_info::TypeInfo;
 use sp_io::hashing::{blake2_256, keccak_256};
-use sp_runtime::{traits::AccountIdConversion, RuntimeDebug};
-use sp_std::prelude::*;
+use sp_runtime::{
+    helpers_128bit::multiply_by_rational_with_rounding,
+    traits::{AccountIdConversion, IdentifyAccount, Saturating, UniqueSaturatedInto, Verify},
+    Rounding, RuntimeDebug,
+};
+use sp_std::{marker::PhantomData, prelude::*};
 
 pub use pallet::*;
 
@@ -17,9 +28,12 @@ use sp_std::convert::TryInto;
 use sp_runtime::traits::Zero;
 
 use codec::{Decode, Encode};
-#[cfg(feature = "std")]
 use serde::{Deserialize, Serialize};
 
+pub mod migrations;
+pub mod runtime_api;
+pub mod secp256k1;
+
 #[cfg(test)]
 mod mock;
 
@@ -31,7 +45,6 @@ mod tests;
 /// `polkadot-js` sends us a `0x01020304`, but the default rust implementation for arrays expects a
 /// `[0x01, 0x02, 0x03, 0x04]`. Here, we use a similar serialization as substrate uses for `vec`,
 /// but we transform it to an array before returning.
-#[cfg(feature = "std")]
 pub mod serialize_array {
     use impl_serde::serialize::{deserialize_check_len, ExpectedLen};
     use serde::Deserializer;
@@ -57,10 +70,94 @@ pub mod serialize_array {
 ///
 /// This gets serialized to the 0x-prefixed hex representation.
 #[derive(
-    Clone, Copy, PartialEq, Eq, Encode, Decode, Default, RuntimeDebug, TypeInfo, MaxEncodedLen,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Encode,
+    Decode,
+    Default,
+    RuntimeDebug,
+    TypeInfo,
+    MaxEncodedLen,
+    Serialize,
+    Deserialize,
+)]
+pub struct AcuityAssetId(#[serde(with = "serialize_array")] [u8; 32]);
+
+/// An address on a foreign chain (i.e. 32 bytes).
+///
+/// This gets serialized to the 0x-prefixed hex representation.
+#[derive(
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Encode,
+    Decode,
+    Default,
+    RuntimeDebug,
+    TypeInfo,
+    MaxEncodedLen,
+    Serialize,
+    Deserialize,
+)]
+pub struct AcuityForeignAddress(#[serde(with = "serialize_array")] [u8; 32]);
+
+/// An Ethereum address (i.e. 20 bytes).
+///
+/// This gets serialized to the 0x-prefixed hex representation.
+#[derive(
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Encode,
+    Decode,
+    Default,
+    RuntimeDebug,
+    TypeInfo,
+    MaxEncodedLen,
+    Serialize,
+    Deserialize,
+)]
+pub struct EthereumAddress(#[serde(with = "serialize_array")] [u8; 20]);
+
+/// A compressed secp256k1 point that locks value until its scalar is revealed.
+///
+/// This gets serialized to the 0x-prefixed hex representation.
+#[derive(
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Encode,
+    Decode,
+    RuntimeDebug,
+    TypeInfo,
+    MaxEncodedLen,
+    Serialize,
+    Deserialize,
+)]
+pub struct AcuityPoint(#[serde(with = "serialize_array")] [u8; 33]);
+
+/// An Ethereum `personal_sign` signature (i.e. 65 bytes).
+///
+/// This gets serialized to the 0x-prefixed hex representation.
+#[derive(
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Encode,
+    Decode,
+    RuntimeDebug,
+    TypeInfo,
+    MaxEncodedLen,
+    Serialize,
+    Deserialize,
 )]
-#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
-pub struct AcuityAssetId(#[cfg_attr(feature = "std", serde(with = "serialize_array"))] [u8; 32]);
+pub struct EthereumSignature(#[serde(with = "serialize_array")] [u8; 65]);
 
 /// A lock ID (i.e. 32 bytes).
 ///
@@ -74,9 +171,20 @@ pub struct AcuityLockId([u8; 32]);
 ///
 /// This gets serialized to the 0x-prefixed hex representation.
 #[derive(
-    Clone, Copy, PartialEq, Eq, Encode, Decode, Default, RuntimeDebug, TypeInfo, MaxEncodedLen,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Encode,
+    Decode,
+    Default,
+    RuntimeDebug,
+    TypeInfo,
+    MaxEncodedLen,
+    Serialize,
+    Deserialize,
 )]
-pub struct AcuityHashedSecret([u8; 32]);
+pub struct AcuityHashedSecret(#[serde(with = "serialize_array")] [u8; 32]);
 
 /// A secret (i.e. 32 bytes).
 ///
@@ -86,8 +194,299 @@ pub struct AcuityHashedSecret([u8; 32]);
 )]
 pub struct AcuitySecret([u8; 32]);
 
-type BalanceOf<T> =
-    <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+/// A sell order signed off-chain by its maker.
+///
+/// The SCALE encoding of the order is what the maker signs.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct SignedOrder<AccountId, Moment> {
+    pub maker: AccountId,
+    pub sell_asset_id: AcuityAssetId,
+    pub buy_asset_id: AcuityAssetId,
+    pub price: u128,
+    pub expiry: Moment,
+    pub nonce: u64,
+}
+
+/// The lifecycle state of a lock.
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum LockState {
+    /// Value is locked.
+    Open,
+    /// Value has been unlocked by the recipient.
+    Unlocked { secret: AcuitySecret },
+    /// Lock has been declined by the recipient.
+    Declined,
+    /// Value has been retrieved by the creator after timing out.
+    Retrieved,
+}
+
+/// Progress of a multi-block migration over locks.
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum MigrationCursor {
+    /// No locks have been migrated yet.
+    Start,
+    /// Locks have been migrated up to and including this lock id.
+    After(AcuityLockId),
+}
+
+/// How lock ids are derived from their fields.
+///
+/// Only hash time-locks have counterparts in the Acuity EVM contracts. NFT, point, threshold and
+/// split lock ids are always derived with `Blake2Scale`.
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum LockIdScheme {
+    /// `blake2_256` over the SCALE-encoded fields.
+    Blake2Scale,
+    /// `keccak256(abi.encodePacked(...))` over the fields, as derived by the Acuity EVM contracts.
+    KeccakAbiPacked,
+}
+
+/// A buyer's request for quotes on an asset pair.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct QuoteRequest<AccountId, Balance, Moment> {
+    pub requester: AccountId,
+    pub sell_asset_id: AcuityAssetId,
+    pub buy_asset_id: AcuityAssetId,
+    pub value: Balance,
+    pub expiry: Moment,
+    pub quote_count: u32,
+}
+
+/// Which recipients of a split lock must consent before it is declined.
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum DeclineConsent {
+    /// Any one recipient can decline.
+    Any,
+    /// Every recipient must decline.
+    All,
+    /// At least this many recipients must decline.
+    Threshold(u32),
+}
+
+/// A lock that pays out to several recipients when its secret is revealed.
+#[derive(
+    CloneNoBound,
+    PartialEqNoBound,
+    EqNoBound,
+    Encode,
+    Decode,
+    RuntimeDebugNoBound,
+    TypeInfo,
+    MaxEncodedLen,
+)]
+#[scale_info(skip_type_params(MaxShares))]
+#[codec(mel_bound(AccountId: MaxEncodedLen, Moment: MaxEncodedLen, Balance: MaxEncodedLen))]
+pub struct SplitLock<AccountId, Moment, Balance, MaxShares>
+where
+    AccountId: Clone + PartialEq + Eq + sp_std::fmt::Debug,
+    Moment: Clone + PartialEq + Eq + sp_std::fmt::Debug,
+    Balance: Clone + PartialEq + Eq + sp_std::fmt::Debug,
+    MaxShares: Get<u32>,
+{
+    pub creator: AccountId,
+    pub hashed_secret: AcuityHashedSecret,
+    pub timeout: Moment,
+    /// The recipients and the value each is paid.
+    pub shares: BoundedVec<(AccountId, Balance), MaxShares>,
+    pub decline_consent: DeclineConsent,
+    /// The recipients that have consented to decline.
+    pub declined_by: BoundedVec<AccountId, MaxShares>,
+}
+
+/// A lock that pays out once a threshold of its hashed secrets have been revealed.
+#[derive(
+    CloneNoBound,
+    PartialEqNoBound,
+    EqNoBound,
+    Encode,
+    Decode,
+    RuntimeDebugNoBound,
+    TypeInfo,
+    MaxEncodedLen,
+)]
+#[scale_info(skip_type_params(MaxHashedSecrets))]
+#[codec(mel_bound(AccountId: MaxEncodedLen, Moment: MaxEncodedLen))]
+pub struct ThresholdLock<AccountId, Moment, MaxHashedSecrets>
+where
+    AccountId: Clone + PartialEq + Eq + sp_std::fmt::Debug,
+    Moment: Clone + PartialEq + Eq + sp_std::fmt::Debug,
+    MaxHashedSecrets: Get<u32>,
+{
+    pub creator: AccountId,
+    pub recipient: AccountId,
+    pub timeout: Moment,
+    /// The hashed secrets and the secrets revealed so far.
+    pub secrets: BoundedVec<(AcuityHashedSecret, Option<AcuitySecret>), MaxHashedSecrets>,
+    /// How many secrets must be revealed to unlock.
+    pub threshold: u32,
+}
+
+/// Progress of a threshold lock.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct ThresholdLockProgress {
+    /// The hashed secrets and the secrets revealed so far.
+    pub secrets: Vec<(AcuityHashedSecret, Option<AcuitySecret>)>,
+    /// How many secrets must be revealed to unlock.
+    pub threshold: u32,
+}
+
+/// The fields a lock id is derived from.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct LockInfo<AccountId, Moment> {
+    pub creator: AccountId,
+    pub recipient: AccountId,
+    pub hashed_secret: AcuityHashedSecret,
+    pub timeout: Moment,
+}
+
+/// A buy lock to create in a batch.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct LockBuySpec<AccountId, Moment, Balance> {
+    pub recipient: AccountId,
+    pub hashed_secret: AcuityHashedSecret,
+    pub timeout: Moment,
+    pub value: Balance,
+    pub sell_asset_id: AcuityAssetId,
+    pub sell_price: u128,
+}
+
+/// A sell lock to create in a batch.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct LockSellSpec<AccountId, Moment, Balance> {
+    pub recipient: AccountId,
+    pub hashed_secret: AcuityHashedSecret,
+    pub timeout: Moment,
+    pub value: Balance,
+    pub buy_asset_id: AcuityAssetId,
+    pub buy_lock_id: AcuityLockId,
+}
+
+/// A market maker's quote in response to a request.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct Quote<Moment> {
+    pub price: u128,
+    pub expiry: Moment,
+}
+
+/// A liquidity provider's terms for an asset.
+#[derive(
+    Clone,
+    PartialEq,
+    Eq,
+    Encode,
+    Decode,
+    RuntimeDebug,
+    TypeInfo,
+    MaxEncodedLen,
+    Serialize,
+    Deserialize,
+)]
+pub struct LiquidityProvider<Balance> {
+    /// The address the provider receives the asset on.
+    pub foreign_address: AcuityForeignAddress,
+    pub min_value: Balance,
+    pub max_value: Balance,
+}
+
+/// Dutch auction pricing for a sell order.
+///
+/// The price decays linearly from `start_price` at `start` to `end_price` at `end`.
+#[derive(
+    Clone,
+    PartialEq,
+    Eq,
+    Encode,
+    Decode,
+    RuntimeDebug,
+    TypeInfo,
+    MaxEncodedLen,
+    Serialize,
+    Deserialize,
+)]
+pub struct DutchAuction<Moment> {
+    pub start_price: u128,
+    pub end_price: u128,
+    pub start: Moment,
+    pub end: Moment,
+}
+
+/// NFTs for runtimes without an NFT pallet.
+///
+/// No item has an owner and minting or transferring fails, so claims on locks cannot be minted
+/// and NFTs cannot be locked.
+pub struct NoNfts<CollectionId, ItemId>(PhantomData<(CollectionId, ItemId)>);
+
+impl<AccountId, CollectionId, ItemId> NftInspect<AccountId> for NoNfts<CollectionId, ItemId> {
+    type ItemId = ItemId;
+    type CollectionId = CollectionId;
+
+    fn owner(_collection: &CollectionId, _item: &ItemId) -> Option<AccountId> {
+        None
+    }
+}
+
+impl<AccountId, CollectionId, ItemId> NftMutate<AccountId> for NoNfts<CollectionId, ItemId> {}
+
+impl<AccountId, CollectionId, ItemId> NftTransfer<AccountId> for NoNfts<CollectionId, ItemId> {
+    fn transfer(
+        _collection: &CollectionId,
+        _item: &ItemId,
+        _destination: &AccountId,
+    ) -> sp_runtime::DispatchResult {
+        Err(sp_runtime::TokenError::Unsupported.into())
+    }
+}
+
+type BalanceOf<T, I = ()> =
+    <<T as Config<I>>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
+type MomentOf<T, I = ()> = <<T as Config<I>>::TimeProvider as Time>::Moment;
+
+type LiquidityProviderOf<T, I = ()> = LiquidityProvider<BalanceOf<T, I>>;
+
+type QuoteRequestOf<T, I = ()> =
+    QuoteRequest<<T as frame_system::Config>::AccountId, BalanceOf<T, I>, MomentOf<T, I>>;
+
+type GenesisLockOf<T, I = ()> = (
+    <T as frame_system::Config>::AccountId,
+    <T as frame_system::Config>::AccountId,
+    AcuityHashedSecret,
+    MomentOf<T, I>,
+    BalanceOf<T, I>,
+);
+
+type GenesisSellAuctionOf<T, I = ()> = (
+    <T as frame_system::Config>::AccountId,
+    AcuityAssetId,
+    DutchAuction<MomentOf<T, I>>,
+);
+
+type LockBuySpecOf<T, I = ()> =
+    LockBuySpec<<T as frame_system::Config>::AccountId, MomentOf<T, I>, BalanceOf<T, I>>;
+
+type LockSellSpecOf<T, I = ()> =
+    LockSellSpec<<T as frame_system::Config>::AccountId, MomentOf<T, I>, BalanceOf<T, I>>;
+
+type ThresholdLockOf<T, I = ()> = ThresholdLock<
+    <T as frame_system::Config>::AccountId,
+    MomentOf<T, I>,
+    <T as Config<I>>::MaxHashedSecrets,
+>;
+
+type SharesOf<T, I = ()> = BoundedVec<
+    (<T as frame_system::Config>::AccountId, BalanceOf<T, I>),
+    <T as Config<I>>::MaxShares,
+>;
+
+/// Big-endian timeout and lock id of a lock in the creator index.
+type CreatorLockKey = ([u8; 16], AcuityLockId);
+
+type SplitLockOf<T, I = ()> = SplitLock<
+    <T as frame_system::Config>::AccountId,
+    MomentOf<T, I>,
+    BalanceOf<T, I>,
+    <T as Config<I>>::MaxShares,
+>;
 
 #[frame_support::pallet(dev_mode)]
 pub mod pallet {
@@ -95,14 +494,23 @@ pub mod pallet {
     use frame_support::pallet_prelude::*;
     use frame_system::pallet_prelude::*;
 
+    /// The current storage version.
+    const STORAGE_VERSION: StorageVersion = StorageVersion::new(2);
+
+    /// Maximum number of entries, and blocks they are due at, pruned from each expiry queue in a
+    /// block.
+    pub const MAX_PRUNED_PER_BLOCK: u32 = 256;
+
     #[pallet::pallet]
-    pub struct Pallet<T>(_);
+    #[pallet::storage_version(STORAGE_VERSION)]
+    pub struct Pallet<T, I = ()>(PhantomData<(T, I)>);
 
     /// Configure the pallet by specifying the parameters and types on which it depends.
     #[pallet::config]
-    pub trait Config: pallet_timestamp::Config + frame_system::Config {
+    pub trait Config<I: 'static = ()>: frame_system::Config {
         /// Because this pallet emits events, it depends on the runtime's definition of an event.
-        type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+        type RuntimeEvent: From<Event<Self, I>>
+            + IsType<<Self as frame_system::Config>::RuntimeEvent>;
 
         /// PalletId for the crowdloan pallet. An appropriate value could be ```PalletId(*b"py/cfund")```
         #[pallet::constant]
@@ -110,51 +518,225 @@ pub mod pallet {
 
         /// The currency type that the charity deals in
         type Currency: Currency<Self::AccountId>;
+
+        /// The source of time that lock timeouts are measured against.
+        type TimeProvider: Time<Moment: MaybeSerializeDeserialize>;
+
+        /// How long before a lock's timeout it stops being unlockable.
+        #[pallet::constant]
+        type UnlockCutoff: Get<<Self::TimeProvider as Time>::Moment>;
+
+        /// How long after a lock's timeout it becomes retrievable.
+        #[pallet::constant]
+        type RefundDelay: Get<<Self::TimeProvider as Time>::Moment>;
+
+        /// Off-chain signature type that makers sign orders with.
+        type OffchainSignature: Verify<Signer = Self::OffchainPublic> + Parameter;
+
+        /// Public key that corresponds to an off-chain signature.
+        type OffchainPublic: IdentifyAccount<AccountId = Self::AccountId>;
+
+        /// Maximum number of quotes that can be submitted for a request.
+        #[pallet::constant]
+        type MaxQuotesPerRequest: Get<u32>;
+
+        /// Maximum number of open quote requests per account.
+        #[pallet::constant]
+        type MaxQuoteRequestsPerAccount: Get<u32>;
+
+        /// Number of blocks after which a quote request is pruned, even if it has not expired.
+        #[pallet::constant]
+        type QuoteRequestLifetime: Get<BlockNumberFor<Self>>;
+
+        /// Whether buy locks may only be sent to registered liquidity providers.
+        #[pallet::constant]
+        type RequireRegisteredRecipient: Get<bool>;
+
+        /// How hash time-lock ids are derived.
+        #[pallet::constant]
+        type LockIdScheme: Get<super::LockIdScheme>;
+
+        /// Identifies the chain in lock ids, for example the genesis hash.
+        #[pallet::constant]
+        type ChainDomain: Get<[u8; 32]>;
+
+        /// How many blocks the state of a settled lock is retained for. A retention of zero is
+        /// treated as one block.
+        #[pallet::constant]
+        type LockStateRetention: Get<BlockNumberFor<Self>>;
+
+        /// Maximum number of locks that can be created in a batch.
+        #[pallet::constant]
+        type MaxLocksPerBatch: Get<u32>;
+
+        /// Maximum number of recipients of a split lock.
+        #[pallet::constant]
+        type MaxShares: Get<u32>;
+
+        /// Maximum number of hashed secrets of a threshold lock.
+        #[pallet::constant]
+        type MaxHashedSecrets: Get<u32>;
+
+        /// The NFTs that can be locked and that represent a recipient's claim on a lock. Use
+        /// [`NoNfts`] if the runtime has no NFT pallet.
+        type Nfts: NftMutate<
+                Self::AccountId,
+                CollectionId = Self::NftCollectionId,
+                ItemId = Self::NftItemId,
+            > + NftTransfer<Self::AccountId>;
+
+        /// The collection that claim NFTs are minted in.
+        type ClaimCollection: Get<Self::NftCollectionId>;
+
+        /// Collection id of an NFT.
+        type NftCollectionId: Parameter + MaxEncodedLen;
+
+        /// Item id of an NFT.
+        type NftItemId: Parameter + MaxEncodedLen + From<u64>;
+    }
+
+    #[pallet::hooks]
+    impl<T: Config<I>, I: 'static> Hooks<BlockNumberFor<T>> for Pallet<T, I> {
+        fn on_initialize(n: BlockNumberFor<T>) -> Weight {
+            // Prune the states of locks whose retention period has ended.
+            let mut reads: u64 = 2;
+            let mut writes: u64 = 2;
+            let (next, blocks, pruned) =
+                Self::prune_due(n, <LockStatePruneCursor<T, I>>::get(), |block, max| {
+                    let mut count = 0;
+                    for (lock_id, ()) in <LockStateExpiry<T, I>>::drain_prefix(block).take(max) {
+                        <LockStates<T, I>>::remove(lock_id);
+                        count += 1;
+                    }
+                    count
+                });
+            <LockStatePruneCursor<T, I>>::put(next);
+            reads = reads
+                .saturating_add(blocks.into())
+                .saturating_add(pruned.into());
+            writes = writes.saturating_add(2u64.saturating_mul(pruned.into()));
+            // Prune quote requests whose lifetime has ended.
+            let (next, blocks, pruned) =
+                Self::prune_due(n, <QuoteRequestPruneCursor<T, I>>::get(), |block, max| {
+                    let mut count = 0;
+                    for (request_id, ()) in
+                        <QuoteRequestExpiry<T, I>>::drain_prefix(block).take(max)
+                    {
+                        reads = reads.saturating_add(1);
+                        if Self::remove_quote_request(request_id) {
+                            Self::deposit_event(Event::QuoteRequestRemoved(request_id));
+                            writes = writes
+                                .saturating_add(2)
+                                .saturating_add(T::MaxQuotesPerRequest::get().into());
+                        }
+                        count += 1;
+                    }
+                    count
+                });
+            <QuoteRequestPruneCursor<T, I>>::put(next);
+            reads = reads
+                .saturating_add(blocks.into())
+                .saturating_add(pruned.into());
+            writes = writes.saturating_add(pruned.into());
+            T::DbWeight::get().reads_writes(reads, writes)
+        }
+
+        fn on_idle(_n: BlockNumberFor<T>, remaining_weight: Weight) -> Weight {
+            migrations::v2::on_idle::<T, I>(remaining_weight)
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn try_state(_n: BlockNumberFor<T>) -> Result<(), sp_runtime::TryRuntimeError> {
+            Self::do_try_state()
+        }
+    }
+
+    #[pallet::genesis_config]
+    #[derive(frame_support::DefaultNoBound)]
+    pub struct GenesisConfig<T: Config<I>, I: 'static = ()> {
+        /// Open locks: \[creator, recipient, hashed_secret, timeout, value\]
+        pub locks: Vec<GenesisLockOf<T, I>>,
+        /// Sell auctions: \[seller, sell_asset_id, auction\]
+        pub sell_auctions: Vec<GenesisSellAuctionOf<T, I>>,
+        /// Registered liquidity providers: \[asset_id, provider, terms\]
+        pub liquidity_providers: Vec<(AcuityAssetId, T::AccountId, LiquidityProviderOf<T, I>)>,
+        #[serde(skip)]
+        pub _config: PhantomData<(T, I)>,
+    }
+
+    #[pallet::genesis_build]
+    impl<T: Config<I>, I: 'static> BuildGenesisConfig for GenesisConfig<T, I> {
+        fn build(&self) {
+            Pallet::<T, I>::provision_fund_account();
+            let mut outstanding = BalanceOf::<T, I>::zero();
+            for (creator, recipient, hashed_secret, timeout, value) in &self.locks {
+                let (timeout, value) = (*timeout, *value);
+                assert!(!value.is_zero(), "Genesis lock has zero value.");
+                let lock_id = Pallet::<T, I>::get_lock_id(
+                    creator.clone(),
+                    recipient.clone(),
+                    *hashed_secret,
+                    timeout,
+                );
+                assert!(
+                    !<LockIdValue<T, I>>::contains_key(lock_id),
+                    "Duplicate genesis lock."
+                );
+                Pallet::<T, I>::insert_lock(
+                    lock_id,
+                    creator,
+                    recipient,
+                    *hashed_secret,
+                    timeout,
+                    value,
+                );
+                outstanding = outstanding.saturating_add(value);
+            }
+            // Fund the pot to match the seeded locks.
+            if !outstanding.is_zero() {
+                let _ =
+                    T::Currency::deposit_creating(&Pallet::<T, I>::fund_account_id(), outstanding);
+            }
+            for (seller, sell_asset_id, auction) in &self.sell_auctions {
+                assert!(
+                    auction.start < auction.end && auction.end_price <= auction.start_price,
+                    "Invalid genesis sell auction."
+                );
+                <SellAuction<T, I>>::insert(seller, sell_asset_id, auction);
+            }
+            for (asset_id, provider, terms) in &self.liquidity_providers {
+                assert!(
+                    terms.min_value <= terms.max_value,
+                    "Invalid genesis liquidity provider swap size."
+                );
+                <LiquidityProviders<T, I>>::insert(asset_id, provider, terms);
+            }
+        }
     }
 
     #[pallet::call]
-    impl<T: Config> Pallet<T> {
+    impl<T: Config<I>, I: 'static> Pallet<T, I> {
         #[pallet::call_index(0)]
         #[pallet::weight(50_000_000)]
         pub fn lock_buy(
             origin: OriginFor<T>,
             recipient: T::AccountId,
             hashed_secret: AcuityHashedSecret,
-            timeout: T::Moment,
-            value: BalanceOf<T>,
+            timeout: MomentOf<T, I>,
+            value: BalanceOf<T, I>,
             sell_asset_id: AcuityAssetId,
             sell_price: u128,
         ) -> DispatchResultWithPostInfo {
             let creator = ensure_signed(origin)?;
-            // Ensure value is nonzero.
-            frame_support::ensure!(!value.is_zero(), Error::<T>::ZeroValue);
-            // Calculate lock_id.
-            let lock_id =
-                Self::get_lock_id(creator.clone(), recipient.clone(), hashed_secret, timeout);
-            // Ensure lock_id is not already in use.
-            ensure!(
-                !LockIdValue::<T>::contains_key(lock_id),
-                Error::<T>::LockAlreadyExists
-            );
-
-            //----------------------------------------
-
-            // Move the value from the sender to the pallet.
-            T::Currency::transfer(&creator, &Self::fund_account_id(), value, AllowDeath)
-                .map_err(|_| DispatchError::Other("Can't transfer value."))?;
-            // Move value into buy lock.
-            <LockIdValue<T>>::insert(lock_id, value);
-            // Log info.
-            Self::deposit_event(Event::LockBuy(
+            Self::do_lock_buy(
                 creator,
                 recipient,
                 hashed_secret,
                 timeout,
                 value,
-                lock_id,
                 sell_asset_id,
                 sell_price,
-            ));
+            )?;
             Ok(().into())
         }
 
@@ -164,30 +746,22 @@ pub mod pallet {
             origin: OriginFor<T>,
             recipient: T::AccountId,
             hashed_secret: AcuityHashedSecret,
-            timeout: T::Moment,
-            value: BalanceOf<T>,
+            timeout: MomentOf<T, I>,
+            value: BalanceOf<T, I>,
             buy_asset_id: AcuityAssetId,
             buy_lock_id: AcuityLockId,
         ) -> DispatchResultWithPostInfo {
             let creator = ensure_signed(origin)?;
-            // Ensure value is nonzero.
-            frame_support::ensure!(!value.is_zero(), Error::<T>::ZeroValue);
-            // Calculate lock_id.
+            // Ensure the lock can be created.
             let lock_id =
-                Self::get_lock_id(creator.clone(), recipient.clone(), hashed_secret, timeout);
-            // Ensure lock_id is not already in use.
-            ensure!(
-                !LockIdValue::<T>::contains_key(lock_id),
-                Error::<T>::LockAlreadyExists
-            );
+                Self::check_new_lock(&creator, &recipient, hashed_secret, timeout, value)?;
 
             //----------------------------------------
 
             // Move the value from the sender to the pallet.
-            T::Currency::transfer(&creator, &Self::fund_account_id(), value, AllowDeath)
-                .map_err(|_| DispatchError::Other("Can't transfer value."))?;
+            Self::transfer_to_pot(&creator, value)?;
             // Move value into sell lock.
-            <LockIdValue<T>>::insert(lock_id, value);
+            Self::insert_lock(lock_id, &creator, &recipient, hashed_secret, timeout, value);
             // Log info.
             Self::deposit_event(Event::LockSell(
                 creator,
@@ -208,25 +782,37 @@ pub mod pallet {
             origin: OriginFor<T>,
             creator: T::AccountId,
             hashed_secret: AcuityHashedSecret,
-            timeout: T::Moment,
+            timeout: MomentOf<T, I>,
         ) -> DispatchResultWithPostInfo {
             let recipient = ensure_signed(origin)?;
             // Calculate lock_id.
-            let lock_id =
-                Self::get_lock_id(creator.clone(), recipient.clone(), hashed_secret, timeout);
+            let lock_id = Self::get_existing_lock_id(
+                creator.clone(),
+                recipient.clone(),
+                hashed_secret,
+                timeout,
+            );
             // Get lock value.
-            let value = match <LockIdValue<T>>::get(lock_id) {
+            let value = match <LockIdValue<T, I>>::get(lock_id) {
                 Some(value) => value,
-                None => return Err(Error::<T>::LockDoesNotExist.into()),
+                None => return Err(Self::missing_lock_error(lock_id).into()),
             };
+            // Ensure the recipient has not sold their claim.
+            if let Some(claim_id) = <LockClaims<T, I>>::get(lock_id) {
+                ensure!(
+                    T::Nfts::owner(&T::ClaimCollection::get(), &claim_id.into())
+                        == Some(recipient.clone()),
+                    Error::<T, I>::NotClaimOwner
+                );
+            }
 
             //----------------------------------------
 
             // Delete lock.
-            LockIdValue::<T>::remove(lock_id);
+            Self::settle_lock(lock_id, LockState::Declined);
+            Self::take_claim(lock_id)?;
             // Transfer the value back to the creator.
-            T::Currency::transfer(&Self::fund_account_id(), &creator, value, AllowDeath)
-                .map_err(|_| DispatchError::Other("Can't transfer value."))?;
+            Self::transfer_from_pot(&creator, value)?;
             // Log info.
             Self::deposit_event(Event::Decline(creator, recipient, lock_id));
             Ok(().into())
@@ -238,7 +824,7 @@ pub mod pallet {
             origin: OriginFor<T>,
             creator: T::AccountId,
             secret: AcuitySecret,
-            timeout: T::Moment,
+            timeout: MomentOf<T, I>,
         ) -> DispatchResultWithPostInfo {
             let recipient = ensure_signed(origin)?;
             // Calculate hashed secret.
@@ -247,26 +833,30 @@ pub mod pallet {
                 .0
                 .copy_from_slice(&keccak_256(&secret.encode()));
             // Calculate lock_id.
-            let lock_id =
-                Self::get_lock_id(creator.clone(), recipient.clone(), hashed_secret, timeout);
+            let lock_id = Self::get_existing_lock_id(
+                creator.clone(),
+                recipient.clone(),
+                hashed_secret,
+                timeout,
+            );
             // Check lock has not timed out.
             frame_support::ensure!(
-                <pallet_timestamp::Pallet<T>>::get() < timeout,
-                Error::<T>::LockTimedOut
+                T::TimeProvider::now() < Self::unlock_deadline(timeout),
+                Error::<T, I>::LockTimedOut
             );
             // Get lock value.
-            let value = match <LockIdValue<T>>::get(lock_id) {
+            let value = match <LockIdValue<T, I>>::get(lock_id) {
                 Some(value) => value,
-                None => return Err(Error::<T>::LockDoesNotExist.into()),
+                None => return Err(Self::missing_lock_error(lock_id).into()),
             };
 
             //----------------------------------------
 
             // Delete lock.
-            LockIdValue::<T>::remove(lock_id);
-            // Transfer the value.
-            T::Currency::transfer(&Self::fund_account_id(), &recipient, value, AllowDeath)
-                .map_err(|_| DispatchError::Other("Can't transfer value."))?;
+            Self::settle_lock(lock_id, LockState::Unlocked { secret });
+            // Transfer the value to the owner of the claim.
+            let payee = Self::take_claim(lock_id)?.unwrap_or_else(|| recipient.clone());
+            Self::transfer_from_pot(&payee, value)?;
             // Log info.
             Self::deposit_event(Event::Unlock(creator, recipient, lock_id, secret));
             Ok(().into())
@@ -278,113 +868,2672 @@ pub mod pallet {
             origin: OriginFor<T>,
             recipient: T::AccountId,
             hashed_secret: AcuityHashedSecret,
-            timeout: T::Moment,
+            timeout: MomentOf<T, I>,
         ) -> DispatchResultWithPostInfo {
             let creator = ensure_signed(origin)?;
             // Calculate lock_id.
-            let lock_id =
-                Self::get_lock_id(creator.clone(), recipient.clone(), hashed_secret, timeout);
+            let lock_id = Self::get_existing_lock_id(
+                creator.clone(),
+                recipient.clone(),
+                hashed_secret,
+                timeout,
+            );
             // Check lock has timed out.
             frame_support::ensure!(
-                <pallet_timestamp::Pallet<T>>::get() >= timeout,
-                Error::<T>::LockNotTimedOut
+                T::TimeProvider::now() >= Self::refund_from(timeout),
+                Error::<T, I>::LockNotTimedOut
             );
             // Get lock value.
-            let value = match <LockIdValue<T>>::get(lock_id) {
+            let value = match <LockIdValue<T, I>>::get(lock_id) {
                 Some(value) => value,
-                None => return Err(Error::<T>::LockDoesNotExist.into()),
+                None => return Err(Self::missing_lock_error(lock_id).into()),
             };
 
             //----------------------------------------
 
             // Delete lock.
-            LockIdValue::<T>::remove(lock_id);
+            Self::settle_lock(lock_id, LockState::Retrieved);
+            Self::take_claim(lock_id)?;
             // Transfer the value.
-            T::Currency::transfer(&Self::fund_account_id(), &creator, value, AllowDeath)
-                .map_err(|_| DispatchError::Other("Can't transfer value."))?;
+            Self::transfer_from_pot(&creator, value)?;
             // Log info.
             Self::deposit_event(Event::Retrieve(creator, recipient, lock_id));
             Ok(().into())
         }
-    }
 
-    #[pallet::event]
-    #[pallet::generate_deposit(pub fn deposit_event)]
-    pub enum Event<T: Config> {
-        /// Value has been locked with sell asset info. \[creator, recipient, hashed_secret, timeout, value, lock_id, sell_asset_id, sell_price\]
-        LockBuy(
-            T::AccountId,
-            T::AccountId,
-            AcuityHashedSecret,
-            T::Moment,
-            BalanceOf<T>,
-            AcuityLockId,
-            AcuityAssetId,
-            u128,
-        ),
-        /// Value has been locked. \[creator, recipient, hashed_secret, timeout, value, lock_id, buy_asset_id, buy_lock_id\]
-        LockSell(
-            T::AccountId,
-            T::AccountId,
-            AcuityHashedSecret,
-            T::Moment,
-            BalanceOf<T>,
-            AcuityLockId,
-            AcuityAssetId,
-            AcuityLockId,
-        ),
-        /// Lock has been declined by the recipient. \[creator, recipient, lock_id\]
-        Decline(T::AccountId, T::AccountId, AcuityLockId),
-        /// Value has been unlocked by the recipient. \[creator, recipient, lock_id, secret\]
-        Unlock(T::AccountId, T::AccountId, AcuityLockId, AcuitySecret),
-        /// Value has been timed out. \[creator, recipient, lock_id\]
-        Retrieve(T::AccountId, T::AccountId, AcuityLockId),
-    }
+        #[pallet::call_index(5)]
+        #[pallet::weight(50_000_000)]
+        pub fn set_sell_auction(
+            origin: OriginFor<T>,
+            sell_asset_id: AcuityAssetId,
+            start_price: u128,
+            end_price: u128,
+            start: MomentOf<T, I>,
+            end: MomentOf<T, I>,
+        ) -> DispatchResultWithPostInfo {
+            let seller = ensure_signed(origin)?;
+            // Ensure the price decays over a nonempty window.
+            ensure!(
+                start_price >= end_price && start < end,
+                Error::<T, I>::InvalidAuction
+            );
 
-    #[pallet::error]
-    pub enum Error<T> {
-        /// Value must not be zero.
-        ZeroValue,
-        /// Value has already been locked with this lockId.
-        LockAlreadyExists,
-        /// No value has already been locked with this lockId.
-        LockDoesNotExist,
-        /// The lock has timed out.
-        LockTimedOut,
-        /// The lock has not timed out.
-        LockNotTimedOut,
-    }
+            //----------------------------------------
 
-    #[pallet::storage]
-    #[pallet::getter(fn lock_id_value)]
-    pub(super) type LockIdValue<T: Config> = StorageMap<_, Identity, AcuityLockId, BalanceOf<T>>;
+            <SellAuction<T, I>>::insert(
+                &seller,
+                sell_asset_id,
+                DutchAuction {
+                    start_price,
+                    end_price,
+                    start,
+                    end,
+                },
+            );
+            // Log info.
+            Self::deposit_event(Event::SellAuction(
+                seller,
+                sell_asset_id,
+                start_price,
+                end_price,
+                start,
+                end,
+            ));
+            Ok(().into())
+        }
 
-    impl<T: Config> Pallet<T> {
-        /// The account ID of the fund pot.
-        ///
-        /// This actually does computation. If you need to keep using it, then make sure you cache the
-        /// value and only call this once.
-        pub fn fund_account_id() -> T::AccountId {
-            T::PalletId::get().into_account_truncating()
+        #[pallet::call_index(6)]
+        #[pallet::weight(50_000_000)]
+        pub fn clear_sell_auction(
+            origin: OriginFor<T>,
+            sell_asset_id: AcuityAssetId,
+        ) -> DispatchResultWithPostInfo {
+            let seller = ensure_signed(origin)?;
+            // Ensure the auction exists.
+            ensure!(
+                <SellAuction<T, I>>::contains_key(&seller, sell_asset_id),
+                Error::<T, I>::AuctionDoesNotExist
+            );
+
+            //----------------------------------------
+
+            <SellAuction<T, I>>::remove(&seller, sell_asset_id);
+            // Log info.
+            Self::deposit_event(Event::SellAuctionCleared(seller, sell_asset_id));
+            Ok(().into())
         }
 
-        pub fn get_lock_id(
-            sender: T::AccountId,
-            recipient: T::AccountId,
+        #[pallet::call_index(7)]
+        #[pallet::weight(50_000_000)]
+        pub fn fill_signed_order(
+            origin: OriginFor<T>,
+            order: SignedOrder<T::AccountId, MomentOf<T, I>>,
+            signature: T::OffchainSignature,
             hashed_secret: AcuityHashedSecret,
-            timeout: T::Moment,
-        ) -> AcuityLockId {
-            let mut lock_id = AcuityLockId::default();
-            lock_id.0.copy_from_slice(&blake2_256(
-                &[
-                    sender.encode(),
-                    recipient.encode(),
-                    hashed_secret.encode(),
-                    timeout.encode(),
-                ]
+            timeout: MomentOf<T, I>,
+            value: BalanceOf<T, I>,
+        ) -> DispatchResultWithPostInfo {
+            let creator = ensure_signed(origin)?;
+            // Check order has not expired.
+            frame_support::ensure!(
+                T::TimeProvider::now() < order.expiry,
+                Error::<T, I>::OrderExpired
+            );
+            // Ensure nonce has not been cancelled.
+            ensure!(
+                order.nonce >= <MakerMinNonce<T, I>>::get(&order.maker),
+                Error::<T, I>::OrderNonceCancelled
+            );
+            // Ensure nonce has not been used.
+            ensure!(
+                !<MakerNonceUsed<T, I>>::contains_key(&order.maker, order.nonce),
+                Error::<T, I>::OrderNonceUsed
+            );
+            // Ensure the maker signed the order.
+            ensure!(
+                signature.verify(&Self::signed_order_message(&order)[..], &order.maker),
+                Error::<T, I>::InvalidOrderSignature
+            );
+
+            //----------------------------------------
+
+            // Lock value for the maker.
+            let lock_id = Self::do_lock_buy(
+                creator,
+                order.maker.clone(),
+                hashed_secret,
+                timeout,
+                value,
+                order.sell_asset_id,
+                order.price,
+            )?;
+            // Consume the nonce.
+            <MakerNonceUsed<T, I>>::insert(&order.maker, order.nonce, ());
+            // Log info.
+            Self::deposit_event(Event::SignedOrderFilled(order.maker, order.nonce, lock_id));
+            Ok(().into())
+        }
+
+        #[pallet::call_index(8)]
+        #[pallet::weight(50_000_000)]
+        pub fn cancel_nonces_up_to(origin: OriginFor<T>, nonce: u64) -> DispatchResultWithPostInfo {
+            let maker = ensure_signed(origin)?;
+            // Ensure nonces have not already been cancelled.
+            ensure!(
+                nonce >= <MakerMinNonce<T, I>>::get(&maker),
+                Error::<T, I>::OrderNonceCancelled
+            );
+
+            //----------------------------------------
+
+            <MakerMinNonce<T, I>>::insert(&maker, nonce.saturating_add(1));
+            // Log info.
+            Self::deposit_event(Event::NoncesCancelled(maker, nonce));
+            Ok(().into())
+        }
+
+        #[pallet::call_index(9)]
+        #[pallet::weight(50_000_000)]
+        pub fn request_quote(
+            origin: OriginFor<T>,
+            sell_asset_id: AcuityAssetId,
+            buy_asset_id: AcuityAssetId,
+            value: BalanceOf<T, I>,
+            expiry: MomentOf<T, I>,
+        ) -> DispatchResultWithPostInfo {
+            let requester = ensure_signed(origin)?;
+            // Ensure value is nonzero.
+            frame_support::ensure!(!value.is_zero(), Error::<T, I>::ZeroValue);
+            // Check request has not expired.
+            frame_support::ensure!(
+                T::TimeProvider::now() < expiry,
+                Error::<T, I>::QuoteRequestExpired
+            );
+            // Ensure the requester has room for another request.
+            ensure!(
+                <QuoteRequestCount<T, I>>::get(&requester) < T::MaxQuoteRequestsPerAccount::get(),
+                Error::<T, I>::TooManyQuoteRequests
+            );
+
+            //----------------------------------------
+
+            let request_id = <NextQuoteRequestId<T, I>>::get();
+            <NextQuoteRequestId<T, I>>::put(request_id.wrapping_add(1));
+            <QuoteRequestCount<T, I>>::mutate(&requester, |count| *count = count.saturating_add(1));
+            let prune_at = <frame_system::Pallet<T>>::block_number()
+                .saturating_add(T::QuoteRequestLifetime::get().max(1u32.into()));
+            <QuoteRequestExpiry<T, I>>::insert(prune_at, request_id, ());
+            <QuoteRequests<T, I>>::insert(
+                request_id,
+                QuoteRequest {
+                    requester: requester.clone(),
+                    sell_asset_id,
+                    buy_asset_id,
+                    value,
+                    expiry,
+                    quote_count: 0,
+                },
+            );
+            // Log info.
+            Self::deposit_event(Event::QuoteRequested(
+                request_id,
+                requester,
+                sell_asset_id,
+                buy_asset_id,
+                value,
+                expiry,
+            ));
+            Ok(().into())
+        }
+
+        #[pallet::call_index(10)]
+        #[pallet::weight(50_000_000)]
+        pub fn submit_quote(
+            origin: OriginFor<T>,
+            request_id: u64,
+            price: u128,
+            expiry: MomentOf<T, I>,
+        ) -> DispatchResultWithPostInfo {
+            let maker = ensure_signed(origin)?;
+            // Get quote request.
+            let mut request = match <QuoteRequests<T, I>>::get(request_id) {
+                Some(request) => request,
+                None => return Err(Error::<T, I>::QuoteRequestDoesNotExist.into()),
+            };
+            let now = T::TimeProvider::now();
+            // Check request has not expired.
+            frame_support::ensure!(now < request.expiry, Error::<T, I>::QuoteRequestExpired);
+            // Check quote has not expired.
+            frame_support::ensure!(now < expiry, Error::<T, I>::QuoteExpired);
+            // A maker replacing their own quote does not take another slot.
+            if !<Quotes<T, I>>::contains_key(request_id, &maker) {
+                ensure!(
+                    request.quote_count < T::MaxQuotesPerRequest::get(),
+                    Error::<T, I>::TooManyQuotes
+                );
+                request.quote_count += 1;
+            }
+
+            //----------------------------------------
+
+            <QuoteRequests<T, I>>::insert(request_id, request);
+            <Quotes<T, I>>::insert(request_id, &maker, Quote { price, expiry });
+            // Log info.
+            Self::deposit_event(Event::QuoteSubmitted(request_id, maker, price, expiry));
+            Ok(().into())
+        }
+
+        #[pallet::call_index(11)]
+        #[pallet::weight(50_000_000)]
+        pub fn accept_quote(
+            origin: OriginFor<T>,
+            request_id: u64,
+            maker: T::AccountId,
+            hashed_secret: AcuityHashedSecret,
+            timeout: MomentOf<T, I>,
+            sell_price: u128,
+        ) -> DispatchResultWithPostInfo {
+            let requester = ensure_signed(origin)?;
+            // Get quote request.
+            let request = match <QuoteRequests<T, I>>::get(request_id) {
+                Some(request) => request,
+                None => return Err(Error::<T, I>::QuoteRequestDoesNotExist.into()),
+            };
+            // Ensure the caller made the request.
+            ensure!(
+                request.requester == requester,
+                Error::<T, I>::NotQuoteRequester
+            );
+            // Get quote.
+            let quote = match <Quotes<T, I>>::get(request_id, &maker) {
+                Some(quote) => quote,
+                None => return Err(Error::<T, I>::QuoteDoesNotExist.into()),
+            };
+            // Check quote has not expired.
+            frame_support::ensure!(
+                T::TimeProvider::now() < quote.expiry,
+                Error::<T, I>::QuoteExpired
+            );
+            // Ensure the sell price is the quoted price.
+            ensure!(sell_price == quote.price, Error::<T, I>::QuotePriceMismatch);
+
+            //----------------------------------------
+
+            // Lock value for the maker.
+            let lock_id = Self::do_lock_buy(
+                requester,
+                maker.clone(),
+                hashed_secret,
+                timeout,
+                request.value,
+                request.sell_asset_id,
+                sell_price,
+            )?;
+            // Delete quote request.
+            Self::remove_quote_request(request_id);
+            // Log info.
+            Self::deposit_event(Event::QuoteAccepted(request_id, maker, lock_id));
+            Ok(().into())
+        }
+
+        #[pallet::call_index(12)]
+        #[pallet::weight(50_000_000)]
+        pub fn cancel_quote_request(
+            origin: OriginFor<T>,
+            request_id: u64,
+        ) -> DispatchResultWithPostInfo {
+            let sender = ensure_signed(origin)?;
+            // Get quote request.
+            let request = match <QuoteRequests<T, I>>::get(request_id) {
+                Some(request) => request,
+                None => return Err(Error::<T, I>::QuoteRequestDoesNotExist.into()),
+            };
+            // Anyone can clean up an expired request.
+            ensure!(
+                request.requester == sender || T::TimeProvider::now() >= request.expiry,
+                Error::<T, I>::NotQuoteRequester
+            );
+
+            //----------------------------------------
+
+            // Delete quote request.
+            Self::remove_quote_request(request_id);
+            // Log info.
+            Self::deposit_event(Event::QuoteRequestRemoved(request_id));
+            Ok(().into())
+        }
+
+        #[pallet::call_index(13)]
+        #[pallet::weight(50_000_000)]
+        pub fn register_liquidity_provider(
+            origin: OriginFor<T>,
+            asset_id: AcuityAssetId,
+            foreign_address: AcuityForeignAddress,
+            min_value: BalanceOf<T, I>,
+            max_value: BalanceOf<T, I>,
+        ) -> DispatchResultWithPostInfo {
+            let provider = ensure_signed(origin)?;
+            // Ensure swap size range is not empty.
+            ensure!(min_value <= max_value, Error::<T, I>::InvalidSwapSize);
+
+            //----------------------------------------
+
+            <LiquidityProviders<T, I>>::insert(
+                asset_id,
+                &provider,
+                LiquidityProvider {
+                    foreign_address,
+                    min_value,
+                    max_value,
+                },
+            );
+            // Log info.
+            Self::deposit_event(Event::LiquidityProviderRegistered(
+                provider,
+                asset_id,
+                foreign_address,
+                min_value,
+                max_value,
+            ));
+            Ok(().into())
+        }
+
+        #[pallet::call_index(14)]
+        #[pallet::weight(50_000_000)]
+        pub fn deregister_liquidity_provider(
+            origin: OriginFor<T>,
+            asset_id: AcuityAssetId,
+        ) -> DispatchResultWithPostInfo {
+            let provider = ensure_signed(origin)?;
+            // Ensure provider is registered.
+            ensure!(
+                <LiquidityProviders<T, I>>::contains_key(asset_id, &provider),
+                Error::<T, I>::ProviderNotRegistered
+            );
+
+            //----------------------------------------
+
+            <LiquidityProviders<T, I>>::remove(asset_id, &provider);
+            // Log info.
+            Self::deposit_event(Event::LiquidityProviderDeregistered(provider, asset_id));
+            Ok(().into())
+        }
+
+        #[pallet::call_index(15)]
+        #[pallet::weight(50_000_000)]
+        pub fn link_ethereum_address(
+            origin: OriginFor<T>,
+            address: EthereumAddress,
+            signature: EthereumSignature,
+        ) -> DispatchResultWithPostInfo {
+            let account = ensure_signed(origin)?;
+            // Ensure the address signed the account id.
+            ensure!(
+                Self::eth_recover(&signature, &account.encode()) == Some(address),
+                Error::<T, I>::InvalidEthereumSignature
+            );
+            // Ensure the address is not linked to another account.
+            ensure!(
+                !<EthereumAddressAccount<T, I>>::contains_key(address),
+                Error::<T, I>::EthereumAddressAlreadyLinked
+            );
+
+            //----------------------------------------
+
+            // Replace any previous link.
+            if let Some(previous) = <AccountEthereumAddress<T, I>>::get(&account) {
+                <EthereumAddressAccount<T, I>>::remove(previous);
+            }
+            <AccountEthereumAddress<T, I>>::insert(&account, address);
+            <EthereumAddressAccount<T, I>>::insert(address, &account);
+            // Log info.
+            Self::deposit_event(Event::EthereumAddressLinked(account, address));
+            Ok(().into())
+        }
+
+        #[pallet::call_index(16)]
+        #[pallet::weight(50_000_000)]
+        pub fn unlink_ethereum_address(origin: OriginFor<T>) -> DispatchResultWithPostInfo {
+            let account = ensure_signed(origin)?;
+            // Get linked address.
+            let address = match <AccountEthereumAddress<T, I>>::get(&account) {
+                Some(address) => address,
+                None => return Err(Error::<T, I>::EthereumAddressNotLinked.into()),
+            };
+
+            //----------------------------------------
+
+            <AccountEthereumAddress<T, I>>::remove(&account);
+            <EthereumAddressAccount<T, I>>::remove(address);
+            // Log info.
+            Self::deposit_event(Event::EthereumAddressUnlinked(account, address));
+            Ok(().into())
+        }
+
+        #[pallet::call_index(17)]
+        #[pallet::weight(Weight::from_parts(50_000_000, 0).saturating_mul(locks.len() as u64))]
+        pub fn lock_buy_batch(
+            origin: OriginFor<T>,
+            locks: BoundedVec<LockBuySpecOf<T, I>, T::MaxLocksPerBatch>,
+        ) -> DispatchResultWithPostInfo {
+            let creator = ensure_signed(origin)?;
+            // Ensure the batch is not empty.
+            ensure!(!locks.is_empty(), Error::<T, I>::EmptyBatch);
+            // Ensure every lock can be created.
+            let mut lock_ids = Vec::with_capacity(locks.len());
+            let mut total = BalanceOf::<T, I>::zero();
+            for lock in &locks {
+                let lock_id = Self::check_lock_buy(
+                    &creator,
+                    &lock.recipient,
+                    lock.hashed_secret,
+                    lock.timeout,
+                    lock.value,
+                    lock.sell_asset_id,
+                    lock.sell_price,
+                )?;
+                ensure!(
+                    !lock_ids.contains(&lock_id),
+                    Error::<T, I>::LockAlreadyExists
+                );
+                lock_ids.push(lock_id);
+                total = total.saturating_add(lock.value);
+            }
+
+            //----------------------------------------
+
+            // Move the total value from the sender to the pallet.
+            Self::transfer_to_pot(&creator, total)?;
+            for (lock, lock_id) in locks.into_iter().zip(lock_ids) {
+                // Move value into buy lock.
+                Self::insert_lock(
+                    lock_id,
+                    &creator,
+                    &lock.recipient,
+                    lock.hashed_secret,
+                    lock.timeout,
+                    lock.value,
+                );
+                <BuyLockAssets<T, I>>::insert(lock_id, lock.sell_asset_id);
+                // Log info.
+                Self::deposit_event(Event::LockBuy(
+                    creator.clone(),
+                    lock.recipient,
+                    lock.hashed_secret,
+                    lock.timeout,
+                    lock.value,
+                    lock_id,
+                    lock.sell_asset_id,
+                    lock.sell_price,
+                ));
+            }
+            Ok(().into())
+        }
+
+        #[pallet::call_index(18)]
+        #[pallet::weight(Weight::from_parts(50_000_000, 0).saturating_mul(locks.len() as u64))]
+        pub fn lock_sell_batch(
+            origin: OriginFor<T>,
+            locks: BoundedVec<LockSellSpecOf<T, I>, T::MaxLocksPerBatch>,
+        ) -> DispatchResultWithPostInfo {
+            let creator = ensure_signed(origin)?;
+            // Ensure the batch is not empty.
+            ensure!(!locks.is_empty(), Error::<T, I>::EmptyBatch);
+            // Ensure every lock can be created.
+            let mut lock_ids = Vec::with_capacity(locks.len());
+            let mut total = BalanceOf::<T, I>::zero();
+            for lock in &locks {
+                let lock_id = Self::check_new_lock(
+                    &creator,
+                    &lock.recipient,
+                    lock.hashed_secret,
+                    lock.timeout,
+                    lock.value,
+                )?;
+                ensure!(
+                    !lock_ids.contains(&lock_id),
+                    Error::<T, I>::LockAlreadyExists
+                );
+                lock_ids.push(lock_id);
+                total = total.saturating_add(lock.value);
+            }
+
+            //----------------------------------------
+
+            // Move the total value from the sender to the pallet.
+            Self::transfer_to_pot(&creator, total)?;
+            for (lock, lock_id) in locks.into_iter().zip(lock_ids) {
+                // Move value into sell lock.
+                Self::insert_lock(
+                    lock_id,
+                    &creator,
+                    &lock.recipient,
+                    lock.hashed_secret,
+                    lock.timeout,
+                    lock.value,
+                );
+                // Log info.
+                Self::deposit_event(Event::LockSell(
+                    creator.clone(),
+                    lock.recipient,
+                    lock.hashed_secret,
+                    lock.timeout,
+                    lock.value,
+                    lock_id,
+                    lock.buy_asset_id,
+                    lock.buy_lock_id,
+                ));
+            }
+            Ok(().into())
+        }
+
+        #[pallet::call_index(19)]
+        #[pallet::weight(Pallet::<T, I>::retrieve_all_expired_weight(*max))]
+        pub fn retrieve_all_expired(origin: OriginFor<T>, max: u32) -> DispatchResultWithPostInfo {
+            let creator = ensure_signed(origin)?;
+            // Find the timed out locks among the up to max earliest locks of the creator.
+            let now = T::TimeProvider::now();
+            let expired: Vec<(AcuityLockId, T::AccountId, BalanceOf<T, I>)> =
+                <CreatorLocks<T, I>>::iter_key_prefix(&creator)
+                    .take(max as usize)
+                    .map_while(|(_, lock_id)| {
+                        let info = <LockInfos<T, I>>::get(lock_id)?;
+                        if now < Self::refund_from(info.timeout) {
+                            return None;
+                        }
+                        let value = <LockIdValue<T, I>>::get(lock_id)?;
+                        Some((lock_id, info.recipient, value))
+                    })
+                    .collect();
+            // Ensure there is something to retrieve.
+            ensure!(!expired.is_empty(), Error::<T, I>::NoExpiredLocks);
+
+            //----------------------------------------
+
+            let mut total = BalanceOf::<T, I>::zero();
+            for (lock_id, _, value) in &expired {
+                // Delete lock.
+                Self::settle_lock(*lock_id, LockState::Retrieved);
+                Self::take_claim(*lock_id)?;
+                total = total.saturating_add(*value);
+            }
+            // Transfer the value.
+            Self::transfer_from_pot(&creator, total)?;
+            // Log info.
+            for (lock_id, recipient, _) in expired {
+                Self::deposit_event(Event::Retrieve(creator.clone(), recipient, lock_id));
+            }
+            Ok(().into())
+        }
+
+        #[pallet::call_index(20)]
+        #[pallet::weight(Pallet::<T, I>::unlock_by_secret_weight(*max))]
+        pub fn unlock_by_secret(
+            origin: OriginFor<T>,
+            secret: AcuitySecret,
+            max: u32,
+        ) -> DispatchResultWithPostInfo {
+            let recipient = ensure_signed(origin)?;
+            // Calculate hashed secret.
+            let mut hashed_secret = AcuityHashedSecret::default();
+            hashed_secret
+                .0
+                .copy_from_slice(&keccak_256(&secret.encode()));
+            // Find up to max open locks with the hashed secret for the caller that have not timed
+            // out.
+            let now = T::TimeProvider::now();
+            let unlocks: Vec<(AcuityLockId, T::AccountId, BalanceOf<T, I>)> =
+                <HashedSecretLocks<T, I>>::iter_key_prefix(hashed_secret)
+                    .filter_map(|lock_id| {
+                        let info = <LockInfos<T, I>>::get(lock_id)?;
+                        if info.recipient != recipient || now >= Self::unlock_deadline(info.timeout)
+                        {
+                            return None;
+                        }
+                        let value = <LockIdValue<T, I>>::get(lock_id)?;
+                        Some((lock_id, info.creator, value))
+                    })
+                    .take(max as usize)
+                    .collect();
+            // Ensure there is something to unlock.
+            ensure!(!unlocks.is_empty(), Error::<T, I>::NoLocksForSecret);
+
+            //----------------------------------------
+
+            let mut total = BalanceOf::<T, I>::zero();
+            for (lock_id, _, value) in &unlocks {
+                // Delete lock.
+                Self::settle_lock(*lock_id, LockState::Unlocked { secret });
+                // Transfer claimed value to the owner of the claim.
+                match Self::take_claim(*lock_id)? {
+                    Some(owner) => Self::transfer_from_pot(&owner, *value)?,
+                    None => total = total.saturating_add(*value),
+                }
+            }
+            // Transfer the value.
+            if !total.is_zero() {
+                Self::transfer_from_pot(&recipient, total)?;
+            }
+            // Log info.
+            for (lock_id, creator, _) in unlocks {
+                Self::deposit_event(Event::Unlock(creator, recipient.clone(), lock_id, secret));
+            }
+            Ok(().into())
+        }
+
+        #[pallet::call_index(21)]
+        #[pallet::weight(Weight::from_parts(50_000_000, 0).saturating_mul(shares.len() as u64))]
+        pub fn lock_split(
+            origin: OriginFor<T>,
+            hashed_secret: AcuityHashedSecret,
+            timeout: MomentOf<T, I>,
+            shares: SharesOf<T, I>,
+            decline_consent: DeclineConsent,
+        ) -> DispatchResultWithPostInfo {
+            let creator = ensure_signed(origin)?;
+            // Ensure there is a recipient.
+            ensure!(!shares.is_empty(), Error::<T, I>::NoShares);
+            // Ensure every share has value that can be paid out and a distinct recipient.
+            let mut value = BalanceOf::<T, I>::zero();
+            for (i, (recipient, share)) in shares.iter().enumerate() {
+                ensure!(!share.is_zero(), Error::<T, I>::ZeroValue);
+                ensure!(
+                    *share >= T::Currency::minimum_balance(),
+                    Error::<T, I>::ShareBelowExistentialDeposit
+                );
+                ensure!(
+                    !shares[..i].iter().any(|(r, _)| r == recipient),
+                    Error::<T, I>::DuplicateShareRecipient
+                );
+                value = value.saturating_add(*share);
+            }
+            // Ensure the decline consent can be met.
+            if let DeclineConsent::Threshold(threshold) = decline_consent {
+                ensure!(
+                    threshold > 0 && threshold as usize <= shares.len(),
+                    Error::<T, I>::InvalidDeclineConsent
+                );
+            }
+            // Calculate lock_id.
+            let lock_id = Self::get_split_lock_id(&creator, hashed_secret, timeout, &shares);
+            // Ensure lock_id is not already in use.
+            ensure!(
+                !LockIdValue::<T, I>::contains_key(lock_id)
+                    && !LockStates::<T, I>::contains_key(lock_id),
+                Error::<T, I>::LockAlreadyExists
+            );
+
+            //----------------------------------------
+
+            // Move the value from the sender to the pallet.
+            Self::transfer_to_pot(&creator, value)?;
+            // Move value into split lock.
+            <LockIdValue<T, I>>::insert(lock_id, value);
+            <LockStates<T, I>>::insert(lock_id, LockState::Open);
+            <SplitLocks<T, I>>::insert(
+                lock_id,
+                SplitLock {
+                    creator: creator.clone(),
+                    hashed_secret,
+                    timeout,
+                    shares,
+                    decline_consent,
+                    declined_by: BoundedVec::default(),
+                },
+            );
+            // Log info.
+            Self::deposit_event(Event::SplitLock(
+                creator,
+                lock_id,
+                hashed_secret,
+                timeout,
+                value,
+            ));
+            Ok(().into())
+        }
+
+        #[pallet::call_index(22)]
+        #[pallet::weight(
+            Weight::from_parts(50_000_000, 0).saturating_mul(T::MaxShares::get().into())
+        )]
+        pub fn unlock_split(
+            origin: OriginFor<T>,
+            lock_id: AcuityLockId,
+            secret: AcuitySecret,
+        ) -> DispatchResultWithPostInfo {
+            let recipient = ensure_signed(origin)?;
+            // Get split lock.
+            let lock = match <SplitLocks<T, I>>::get(lock_id) {
+                Some(lock) => lock,
+                None => return Err(Self::missing_lock_error(lock_id).into()),
+            };
+            // Ensure the caller is a recipient.
+            ensure!(
+                lock.shares.iter().any(|(r, _)| *r == recipient),
+                Error::<T, I>::NotShareRecipient
+            );
+            // Check the secret.
+            ensure!(
+                keccak_256(&secret.encode()) == lock.hashed_secret.0,
+                Error::<T, I>::WrongSecret
+            );
+            // Check lock has not timed out.
+            ensure!(
+                T::TimeProvider::now() < Self::unlock_deadline(lock.timeout),
+                Error::<T, I>::LockTimedOut
+            );
+
+            //----------------------------------------
+
+            // Delete lock.
+            <SplitLocks<T, I>>::remove(lock_id);
+            Self::settle_lock(lock_id, LockState::Unlocked { secret });
+            // Transfer the shares.
+            for (recipient, share) in &lock.shares {
+                Self::transfer_from_pot(recipient, *share)?;
+            }
+            // Log info.
+            Self::deposit_event(Event::SplitUnlock(lock.creator, lock_id, secret));
+            Ok(().into())
+        }
+
+        #[pallet::call_index(23)]
+        #[pallet::weight(50_000_000)]
+        pub fn decline_split(
+            origin: OriginFor<T>,
+            lock_id: AcuityLockId,
+        ) -> DispatchResultWithPostInfo {
+            let recipient = ensure_signed(origin)?;
+            // Get split lock.
+            let mut lock = match <SplitLocks<T, I>>::get(lock_id) {
+                Some(lock) => lock,
+                None => return Err(Self::missing_lock_error(lock_id).into()),
+            };
+            // Ensure the caller is a recipient.
+            ensure!(
+                lock.shares.iter().any(|(r, _)| *r == recipient),
+                Error::<T, I>::NotShareRecipient
+            );
+            // Ensure the caller has not already consented.
+            ensure!(
+                !lock.declined_by.contains(&recipient),
+                Error::<T, I>::AlreadyConsented
+            );
+            // Get lock value.
+            let value = match <LockIdValue<T, I>>::get(lock_id) {
+                Some(value) => value,
+                None => return Err(Self::missing_lock_error(lock_id).into()),
+            };
+
+            //----------------------------------------
+
+            // There is a slot for every recipient.
+            let _ = lock.declined_by.try_push(recipient.clone());
+            let consents = lock.declined_by.len();
+            let declined = match lock.decline_consent {
+                DeclineConsent::Any => true,
+                DeclineConsent::All => consents == lock.shares.len(),
+                DeclineConsent::Threshold(threshold) => consents >= threshold as usize,
+            };
+            // Log info.
+            Self::deposit_event(Event::SplitDeclineConsent(lock_id, recipient));
+            if declined {
+                // Delete lock.
+                <SplitLocks<T, I>>::remove(lock_id);
+                Self::settle_lock(lock_id, LockState::Declined);
+                // Transfer the value back to the creator.
+                Self::transfer_from_pot(&lock.creator, value)?;
+                // Log info.
+                Self::deposit_event(Event::SplitDecline(lock.creator, lock_id));
+            } else {
+                <SplitLocks<T, I>>::insert(lock_id, lock);
+            }
+            Ok(().into())
+        }
+
+        #[pallet::call_index(24)]
+        #[pallet::weight(50_000_000)]
+        pub fn retrieve_split(
+            origin: OriginFor<T>,
+            lock_id: AcuityLockId,
+        ) -> DispatchResultWithPostInfo {
+            let creator = ensure_signed(origin)?;
+            // Get split lock.
+            let lock = match <SplitLocks<T, I>>::get(lock_id) {
+                Some(lock) if lock.creator == creator => lock,
+                _ => return Err(Self::missing_lock_error(lock_id).into()),
+            };
+            // Check lock has timed out.
+            ensure!(
+                T::TimeProvider::now() >= Self::refund_from(lock.timeout),
+                Error::<T, I>::LockNotTimedOut
+            );
+            // Get lock value.
+            let value = match <LockIdValue<T, I>>::get(lock_id) {
+                Some(value) => value,
+                None => return Err(Self::missing_lock_error(lock_id).into()),
+            };
+
+            //----------------------------------------
+
+            // Delete lock.
+            <SplitLocks<T, I>>::remove(lock_id);
+            Self::settle_lock(lock_id, LockState::Retrieved);
+            // Transfer the value.
+            Self::transfer_from_pot(&creator, value)?;
+            // Log info.
+            Self::deposit_event(Event::SplitRetrieve(creator, lock_id));
+            Ok(().into())
+        }
+
+        #[pallet::call_index(25)]
+        #[pallet::weight(50_000_000)]
+        pub fn lock_threshold(
+            origin: OriginFor<T>,
+            recipient: T::AccountId,
+            hashed_secrets: BoundedVec<AcuityHashedSecret, T::MaxHashedSecrets>,
+            threshold: u32,
+            timeout: MomentOf<T, I>,
+            value: BalanceOf<T, I>,
+        ) -> DispatchResultWithPostInfo {
+            let creator = ensure_signed(origin)?;
+            // Ensure value is nonzero.
+            ensure!(!value.is_zero(), Error::<T, I>::ZeroValue);
+            // Ensure there is a distinct hashed secret for every secret.
+            ensure!(!hashed_secrets.is_empty(), Error::<T, I>::NoHashedSecrets);
+            for (i, hashed_secret) in hashed_secrets.iter().enumerate() {
+                ensure!(
+                    !hashed_secrets[..i].contains(hashed_secret),
+                    Error::<T, I>::DuplicateHashedSecret
+                );
+            }
+            // Ensure the threshold can be met.
+            ensure!(
+                threshold > 0 && threshold as usize <= hashed_secrets.len(),
+                Error::<T, I>::InvalidThreshold
+            );
+            // Calculate lock_id.
+            let lock_id = Self::get_threshold_lock_id(
+                &creator,
+                &recipient,
+                &hashed_secrets,
+                threshold,
+                timeout,
+            );
+            // Ensure lock_id is not already in use.
+            ensure!(
+                !LockIdValue::<T, I>::contains_key(lock_id)
+                    && !LockStates::<T, I>::contains_key(lock_id),
+                Error::<T, I>::LockAlreadyExists
+            );
+
+            //----------------------------------------
+
+            // Move the value from the sender to the pallet.
+            Self::transfer_to_pot(&creator, value)?;
+            // Move value into threshold lock.
+            <LockIdValue<T, I>>::insert(lock_id, value);
+            <LockStates<T, I>>::insert(lock_id, LockState::Open);
+            let secrets = hashed_secrets
+                .into_iter()
+                .map(|hashed_secret| (hashed_secret, None))
+                .collect::<Vec<_>>();
+            <ThresholdLocks<T, I>>::insert(
+                lock_id,
+                ThresholdLock {
+                    creator: creator.clone(),
+                    recipient: recipient.clone(),
+                    timeout,
+                    // There is a slot for every hashed secret.
+                    secrets: BoundedVec::truncate_from(secrets),
+                    threshold,
+                },
+            );
+            // Log info.
+            Self::deposit_event(Event::ThresholdLock(
+                creator, recipient, lock_id, timeout, value, threshold,
+            ));
+            Ok(().into())
+        }
+
+        #[pallet::call_index(26)]
+        #[pallet::weight(50_000_000)]
+        pub fn reveal_secret(
+            origin: OriginFor<T>,
+            lock_id: AcuityLockId,
+            secret: AcuitySecret,
+        ) -> DispatchResultWithPostInfo {
+            ensure_signed(origin)?;
+            // Get threshold lock.
+            let mut lock = match <ThresholdLocks<T, I>>::get(lock_id) {
+                Some(lock) => lock,
+                None => return Err(Self::missing_lock_error(lock_id).into()),
+            };
+            // Calculate hashed secret.
+            let mut hashed_secret = AcuityHashedSecret::default();
+            hashed_secret
+                .0
+                .copy_from_slice(&keccak_256(&secret.encode()));
+            // Find the hashed secret.
+            let slot = match lock.secrets.iter_mut().find(|(h, _)| *h == hashed_secret) {
+                Some(slot) => slot,
+                None => return Err(Error::<T, I>::WrongSecret.into()),
+            };
+            // Ensure it has not been revealed.
+            ensure!(slot.1.is_none(), Error::<T, I>::SecretAlreadyRevealed);
+            // Check lock has not timed out.
+            ensure!(
+                T::TimeProvider::now() < Self::unlock_deadline(lock.timeout),
+                Error::<T, I>::LockTimedOut
+            );
+            // Get lock value.
+            let value = match <LockIdValue<T, I>>::get(lock_id) {
+                Some(value) => value,
+                None => return Err(Self::missing_lock_error(lock_id).into()),
+            };
+
+            //----------------------------------------
+
+            slot.1 = Some(secret);
+            // Log info.
+            Self::deposit_event(Event::SecretRevealed(lock_id, hashed_secret, secret));
+            let revealed = lock.secrets.iter().filter(|(_, s)| s.is_some()).count();
+            if revealed as u32 >= lock.threshold {
+                // Delete lock.
+                <ThresholdLocks<T, I>>::remove(lock_id);
+                Self::settle_lock(lock_id, LockState::Unlocked { secret });
+                // Transfer the value.
+                Self::transfer_from_pot(&lock.recipient, value)?;
+                // Log info.
+                Self::deposit_event(Event::Unlock(lock.creator, lock.recipient, lock_id, secret));
+            } else {
+                <ThresholdLocks<T, I>>::insert(lock_id, lock);
+            }
+            Ok(().into())
+        }
+
+        #[pallet::call_index(27)]
+        #[pallet::weight(50_000_000)]
+        pub fn decline_threshold(
+            origin: OriginFor<T>,
+            lock_id: AcuityLockId,
+        ) -> DispatchResultWithPostInfo {
+            let recipient = ensure_signed(origin)?;
+            // Get threshold lock.
+            let lock = match <ThresholdLocks<T, I>>::get(lock_id) {
+                Some(lock) if lock.recipient == recipient => lock,
+                _ => return Err(Self::missing_lock_error(lock_id).into()),
+            };
+            // Get lock value.
+            let value = match <LockIdValue<T, I>>::get(lock_id) {
+                Some(value) => value,
+                None => return Err(Self::missing_lock_error(lock_id).into()),
+            };
+
+            //----------------------------------------
+
+            // Delete lock.
+            <ThresholdLocks<T, I>>::remove(lock_id);
+            Self::settle_lock(lock_id, LockState::Declined);
+            // Transfer the value back to the creator.
+            Self::transfer_from_pot(&lock.creator, value)?;
+            // Log info.
+            Self::deposit_event(Event::Decline(lock.creator, recipient, lock_id));
+            Ok(().into())
+        }
+
+        #[pallet::call_index(28)]
+        #[pallet::weight(50_000_000)]
+        pub fn retrieve_threshold(
+            origin: OriginFor<T>,
+            lock_id: AcuityLockId,
+        ) -> DispatchResultWithPostInfo {
+            let creator = ensure_signed(origin)?;
+            // Get threshold lock.
+            let lock = match <ThresholdLocks<T, I>>::get(lock_id) {
+                Some(lock) if lock.creator == creator => lock,
+                _ => return Err(Self::missing_lock_error(lock_id).into()),
+            };
+            // Check lock has timed out.
+            ensure!(
+                T::TimeProvider::now() >= Self::refund_from(lock.timeout),
+                Error::<T, I>::LockNotTimedOut
+            );
+            // Get lock value.
+            let value = match <LockIdValue<T, I>>::get(lock_id) {
+                Some(value) => value,
+                None => return Err(Self::missing_lock_error(lock_id).into()),
+            };
+
+            //----------------------------------------
+
+            // Delete lock.
+            <ThresholdLocks<T, I>>::remove(lock_id);
+            Self::settle_lock(lock_id, LockState::Retrieved);
+            // Transfer the value.
+            Self::transfer_from_pot(&creator, value)?;
+            // Log info.
+            Self::deposit_event(Event::Retrieve(creator, lock.recipient, lock_id));
+            Ok(().into())
+        }
+
+        #[pallet::call_index(29)]
+        #[pallet::weight(50_000_000)]
+        pub fn lock_ptlc(
+            origin: OriginFor<T>,
+            recipient: T::AccountId,
+            point: AcuityPoint,
+            timeout: MomentOf<T, I>,
+            value: BalanceOf<T, I>,
+        ) -> DispatchResultWithPostInfo {
+            let creator = ensure_signed(origin)?;
+            // Ensure value is nonzero.
+            ensure!(!value.is_zero(), Error::<T, I>::ZeroValue);
+            // Calculate lock_id.
+            let lock_id = Self::get_ptlc_lock_id(&creator, &recipient, point, timeout);
+            // Ensure lock_id is not already in use.
+            ensure!(
+                !LockIdValue::<T, I>::contains_key(lock_id)
+                    && !LockStates::<T, I>::contains_key(lock_id),
+                Error::<T, I>::LockAlreadyExists
+            );
+
+            //----------------------------------------
+
+            // Move the value from the sender to the pallet.
+            Self::transfer_to_pot(&creator, value)?;
+            // Move value into point lock.
+            <LockIdValue<T, I>>::insert(lock_id, value);
+            <LockStates<T, I>>::insert(lock_id, LockState::Open);
+            // Log info.
+            Self::deposit_event(Event::PtlcLock(
+                creator, recipient, point, timeout, value, lock_id,
+            ));
+            Ok(().into())
+        }
+
+        #[pallet::call_index(30)]
+        #[pallet::weight(50_000_000)]
+        pub fn unlock_ptlc(
+            origin: OriginFor<T>,
+            creator: T::AccountId,
+            scalar: AcuitySecret,
+            timeout: MomentOf<T, I>,
+        ) -> DispatchResultWithPostInfo {
+            let recipient = ensure_signed(origin)?;
+            // Calculate point.
+            let point = match secp256k1::point_from_scalar(&scalar.0) {
+                Some(point) => AcuityPoint(point),
+                None => return Err(Error::<T, I>::InvalidScalar.into()),
+            };
+            // Calculate lock_id.
+            let lock_id = Self::get_ptlc_lock_id(&creator, &recipient, point, timeout);
+            // Check lock has not timed out.
+            ensure!(
+                T::TimeProvider::now() < Self::unlock_deadline(timeout),
+                Error::<T, I>::LockTimedOut
+            );
+            // Get lock value.
+            let value = match <LockIdValue<T, I>>::get(lock_id) {
+                Some(value) => value,
+                None => return Err(Self::missing_lock_error(lock_id).into()),
+            };
+
+            //----------------------------------------
+
+            // Delete lock.
+            Self::settle_lock(lock_id, LockState::Unlocked { secret: scalar });
+            // Transfer the value.
+            Self::transfer_from_pot(&recipient, value)?;
+            // Log info.
+            Self::deposit_event(Event::Unlock(creator, recipient, lock_id, scalar));
+            Ok(().into())
+        }
+
+        #[pallet::call_index(31)]
+        #[pallet::weight(50_000_000)]
+        pub fn decline_ptlc(
+            origin: OriginFor<T>,
+            creator: T::AccountId,
+            point: AcuityPoint,
+            timeout: MomentOf<T, I>,
+        ) -> DispatchResultWithPostInfo {
+            let recipient = ensure_signed(origin)?;
+            // Calculate lock_id.
+            let lock_id = Self::get_ptlc_lock_id(&creator, &recipient, point, timeout);
+            // Get lock value.
+            let value = match <LockIdValue<T, I>>::get(lock_id) {
+                Some(value) => value,
+                None => return Err(Self::missing_lock_error(lock_id).into()),
+            };
+
+            //----------------------------------------
+
+            // Delete lock.
+            Self::settle_lock(lock_id, LockState::Declined);
+            // Transfer the value back to the creator.
+            Self::transfer_from_pot(&creator, value)?;
+            // Log info.
+            Self::deposit_event(Event::Decline(creator, recipient, lock_id));
+            Ok(().into())
+        }
+
+        #[pallet::call_index(32)]
+        #[pallet::weight(50_000_000)]
+        pub fn retrieve_ptlc(
+            origin: OriginFor<T>,
+            recipient: T::AccountId,
+            point: AcuityPoint,
+            timeout: MomentOf<T, I>,
+        ) -> DispatchResultWithPostInfo {
+            let creator = ensure_signed(origin)?;
+            // Calculate lock_id.
+            let lock_id = Self::get_ptlc_lock_id(&creator, &recipient, point, timeout);
+            // Check lock has timed out.
+            ensure!(
+                T::TimeProvider::now() >= Self::refund_from(timeout),
+                Error::<T, I>::LockNotTimedOut
+            );
+            // Get lock value.
+            let value = match <LockIdValue<T, I>>::get(lock_id) {
+                Some(value) => value,
+                None => return Err(Self::missing_lock_error(lock_id).into()),
+            };
+
+            //----------------------------------------
+
+            // Delete lock.
+            Self::settle_lock(lock_id, LockState::Retrieved);
+            // Transfer the value.
+            Self::transfer_from_pot(&creator, value)?;
+            // Log info.
+            Self::deposit_event(Event::Retrieve(creator, recipient, lock_id));
+            Ok(().into())
+        }
+
+        #[pallet::call_index(33)]
+        #[pallet::weight(50_000_000)]
+        pub fn top_up(
+            origin: OriginFor<T>,
+            recipient: T::AccountId,
+            hashed_secret: AcuityHashedSecret,
+            timeout: MomentOf<T, I>,
+            extra: BalanceOf<T, I>,
+        ) -> DispatchResultWithPostInfo {
+            let creator = ensure_signed(origin)?;
+            // Ensure extra is nonzero.
+            ensure!(!extra.is_zero(), Error::<T, I>::ZeroValue);
+            // Calculate lock_id.
+            let lock_id = Self::get_existing_lock_id(
+                creator.clone(),
+                recipient.clone(),
+                hashed_secret,
+                timeout,
+            );
+            // Check lock can still be unlocked.
+            ensure!(
+                T::TimeProvider::now() < Self::unlock_deadline(timeout),
+                Error::<T, I>::LockTimedOut
+            );
+            // Get lock value.
+            let value = match <LockIdValue<T, I>>::get(lock_id) {
+                Some(value) => value.saturating_add(extra),
+                None => return Err(Self::missing_lock_error(lock_id).into()),
+            };
+            // Ensure the recipient of a buy lock provides liquidity of the new size.
+            if let Some(sell_asset_id) = <BuyLockAssets<T, I>>::get(lock_id) {
+                Self::check_swap_size(&recipient, sell_asset_id, value)?;
+            }
+
+            //----------------------------------------
+
+            // Move the extra value from the sender to the pallet.
+            Self::transfer_to_pot(&creator, extra)?;
+            <LockIdValue<T, I>>::insert(lock_id, value);
+            // Log info.
+            Self::deposit_event(Event::LockToppedUp(
+                creator, recipient, lock_id, extra, value,
+            ));
+            Ok(().into())
+        }
+
+        #[pallet::call_index(34)]
+        #[pallet::weight(50_000_000)]
+        pub fn mint_claim(
+            origin: OriginFor<T>,
+            creator: T::AccountId,
+            hashed_secret: AcuityHashedSecret,
+            timeout: MomentOf<T, I>,
+        ) -> DispatchResultWithPostInfo {
+            let recipient = ensure_signed(origin)?;
+            // Calculate lock_id.
+            let lock_id = Self::get_existing_lock_id(
+                creator.clone(),
+                recipient.clone(),
+                hashed_secret,
+                timeout,
+            );
+            // Ensure the lock is open.
+            ensure!(
+                <LockIdValue<T, I>>::contains_key(lock_id),
+                Self::missing_lock_error(lock_id)
+            );
+            // Ensure the claim has not been minted.
+            ensure!(
+                !<LockClaims<T, I>>::contains_key(lock_id),
+                Error::<T, I>::ClaimAlreadyMinted
+            );
+
+            //----------------------------------------
+
+            let claim_id = <NextClaimId<T, I>>::get();
+            T::Nfts::mint_into(&T::ClaimCollection::get(), &claim_id.into(), &recipient)?;
+            <NextClaimId<T, I>>::put(claim_id.saturating_add(1));
+            <LockClaims<T, I>>::insert(lock_id, claim_id);
+            // Log info.
+            Self::deposit_event(Event::ClaimMinted(lock_id, recipient, claim_id));
+            Ok(().into())
+        }
+
+        #[pallet::call_index(35)]
+        #[pallet::weight(50_000_000)]
+        pub fn unlock_claim(
+            origin: OriginFor<T>,
+            creator: T::AccountId,
+            recipient: T::AccountId,
+            secret: AcuitySecret,
+            timeout: MomentOf<T, I>,
+        ) -> DispatchResultWithPostInfo {
+            ensure_signed(origin)?;
+            // Calculate hashed secret.
+            let mut hashed_secret = AcuityHashedSecret::default();
+            hashed_secret
+                .0
+                .copy_from_slice(&keccak_256(&secret.encode()));
+            // Calculate lock_id.
+            let lock_id = Self::get_existing_lock_id(
+                creator.clone(),
+                recipient.clone(),
+                hashed_secret,
+                timeout,
+            );
+            // Check lock has not timed out.
+            ensure!(
+                T::TimeProvider::now() < Self::unlock_deadline(timeout),
+                Error::<T, I>::LockTimedOut
+            );
+            // Get lock value.
+            let value = match <LockIdValue<T, I>>::get(lock_id) {
+                Some(value) => value,
+                None => return Err(Self::missing_lock_error(lock_id).into()),
+            };
+            // Ensure the claim has been minted.
+            ensure!(
+                <LockClaims<T, I>>::contains_key(lock_id),
+                Error::<T, I>::ClaimDoesNotExist
+            );
+
+            //----------------------------------------
+
+            // Delete lock.
+            Self::settle_lock(lock_id, LockState::Unlocked { secret });
+            // Transfer the value to the owner of the claim.
+            let payee = Self::take_claim(lock_id)?.unwrap_or_else(|| recipient.clone());
+            Self::transfer_from_pot(&payee, value)?;
+            // Log info.
+            Self::deposit_event(Event::Unlock(creator, recipient, lock_id, secret));
+            Ok(().into())
+        }
+
+        #[pallet::call_index(36)]
+        #[pallet::weight(50_000_000)]
+        pub fn lock_nft(
+            origin: OriginFor<T>,
+            collection: T::NftCollectionId,
+            item: T::NftItemId,
+            recipient: T::AccountId,
+            hashed_secret: AcuityHashedSecret,
+            timeout: MomentOf<T, I>,
+        ) -> DispatchResultWithPostInfo {
+            let creator = ensure_signed(origin)?;
+            // Calculate lock_id.
+            let lock_id = Self::get_nft_lock_id(&creator, &recipient, hashed_secret, timeout);
+            // Ensure lock_id is not already in use.
+            ensure!(
+                !NftLocks::<T, I>::contains_key(lock_id)
+                    && !LockStates::<T, I>::contains_key(lock_id),
+                Error::<T, I>::LockAlreadyExists
+            );
+            // Ensure the NFT is not a claim on a lock.
+            ensure!(
+                collection != T::ClaimCollection::get(),
+                Error::<T, I>::ClaimNftNotLockable
+            );
+            // Ensure the sender owns the NFT.
+            ensure!(
+                T::Nfts::owner(&collection, &item) == Some(creator.clone()),
+                Error::<T, I>::NotNftOwner
+            );
+
+            //----------------------------------------
+
+            // Move the NFT from the sender to the pallet.
+            T::Nfts::transfer(&collection, &item, &Self::fund_account_id())?;
+            <NftLocks<T, I>>::insert(lock_id, (collection.clone(), item.clone()));
+            <LockStates<T, I>>::insert(lock_id, LockState::Open);
+            // Log info.
+            Self::deposit_event(Event::NftLock(
+                creator,
+                recipient,
+                hashed_secret,
+                timeout,
+                collection,
+                item,
+                lock_id,
+            ));
+            Ok(().into())
+        }
+
+        #[pallet::call_index(37)]
+        #[pallet::weight(50_000_000)]
+        pub fn unlock_nft(
+            origin: OriginFor<T>,
+            creator: T::AccountId,
+            secret: AcuitySecret,
+            timeout: MomentOf<T, I>,
+        ) -> DispatchResultWithPostInfo {
+            let recipient = ensure_signed(origin)?;
+            // Calculate hashed secret.
+            let mut hashed_secret = AcuityHashedSecret::default();
+            hashed_secret
+                .0
+                .copy_from_slice(&keccak_256(&secret.encode()));
+            // Calculate lock_id.
+            let lock_id = Self::get_nft_lock_id(&creator, &recipient, hashed_secret, timeout);
+            // Check lock has not timed out.
+            ensure!(
+                T::TimeProvider::now() < Self::unlock_deadline(timeout),
+                Error::<T, I>::LockTimedOut
+            );
+            // Get locked NFT.
+            let (collection, item) = match <NftLocks<T, I>>::get(lock_id) {
+                Some(nft) => nft,
+                None => return Err(Self::missing_lock_error(lock_id).into()),
+            };
+
+            //----------------------------------------
+
+            // Delete lock.
+            <NftLocks<T, I>>::remove(lock_id);
+            Self::settle_lock(lock_id, LockState::Unlocked { secret });
+            // Transfer the NFT.
+            T::Nfts::transfer(&collection, &item, &recipient)?;
+            // Log info.
+            Self::deposit_event(Event::NftUnlock(
+                creator, recipient, lock_id, secret, collection, item,
+            ));
+            Ok(().into())
+        }
+
+        #[pallet::call_index(38)]
+        #[pallet::weight(50_000_000)]
+        pub fn decline_nft(
+            origin: OriginFor<T>,
+            creator: T::AccountId,
+            hashed_secret: AcuityHashedSecret,
+            timeout: MomentOf<T, I>,
+        ) -> DispatchResultWithPostInfo {
+            let recipient = ensure_signed(origin)?;
+            // Calculate lock_id.
+            let lock_id = Self::get_nft_lock_id(&creator, &recipient, hashed_secret, timeout);
+            // Get locked NFT.
+            let (collection, item) = match <NftLocks<T, I>>::get(lock_id) {
+                Some(nft) => nft,
+                None => return Err(Self::missing_lock_error(lock_id).into()),
+            };
+
+            //----------------------------------------
+
+            // Delete lock.
+            <NftLocks<T, I>>::remove(lock_id);
+            Self::settle_lock(lock_id, LockState::Declined);
+            // Transfer the NFT back to the creator.
+            T::Nfts::transfer(&collection, &item, &creator)?;
+            // Log info.
+            Self::deposit_event(Event::NftDecline(
+                creator, recipient, lock_id, collection, item,
+            ));
+            Ok(().into())
+        }
+
+        #[pallet::call_index(39)]
+        #[pallet::weight(50_000_000)]
+        pub fn retrieve_nft(
+            origin: OriginFor<T>,
+            recipient: T::AccountId,
+            hashed_secret: AcuityHashedSecret,
+            timeout: MomentOf<T, I>,
+        ) -> DispatchResultWithPostInfo {
+            let creator = ensure_signed(origin)?;
+            // Calculate lock_id.
+            let lock_id = Self::get_nft_lock_id(&creator, &recipient, hashed_secret, timeout);
+            // Check lock has timed out.
+            ensure!(
+                T::TimeProvider::now() >= Self::refund_from(timeout),
+                Error::<T, I>::LockNotTimedOut
+            );
+            // Get locked NFT.
+            let (collection, item) = match <NftLocks<T, I>>::get(lock_id) {
+                Some(nft) => nft,
+                None => return Err(Self::missing_lock_error(lock_id).into()),
+            };
+
+            //----------------------------------------
+
+            // Delete lock.
+            <NftLocks<T, I>>::remove(lock_id);
+            Self::settle_lock(lock_id, LockState::Retrieved);
+            // Transfer the NFT.
+            T::Nfts::transfer(&collection, &item, &creator)?;
+            // Log info.
+            Self::deposit_event(Event::NftRetrieve(
+                creator, recipient, lock_id, collection, item,
+            ));
+            Ok(().into())
+        }
+    }
+
+    #[pallet::event]
+    #[pallet::generate_deposit(pub fn deposit_event)]
+    pub enum Event<T: Config<I>, I: 'static = ()> {
+        /// Value has been locked with sell asset info. \[creator, recipient, hashed_secret, timeout, value, lock_id, sell_asset_id, sell_price\]
+        LockBuy(
+            T::AccountId,
+            T::AccountId,
+            AcuityHashedSecret,
+            MomentOf<T, I>,
+            BalanceOf<T, I>,
+            AcuityLockId,
+            AcuityAssetId,
+            u128,
+        ),
+        /// Value has been locked. \[creator, recipient, hashed_secret, timeout, value, lock_id, buy_asset_id, buy_lock_id\]
+        LockSell(
+            T::AccountId,
+            T::AccountId,
+            AcuityHashedSecret,
+            MomentOf<T, I>,
+            BalanceOf<T, I>,
+            AcuityLockId,
+            AcuityAssetId,
+            AcuityLockId,
+        ),
+        /// Lock has been declined by the recipient. \[creator, recipient, lock_id\]
+        Decline(T::AccountId, T::AccountId, AcuityLockId),
+        /// Value has been unlocked by the recipient. \[creator, recipient, lock_id, secret\]
+        Unlock(T::AccountId, T::AccountId, AcuityLockId, AcuitySecret),
+        /// Value has been timed out. \[creator, recipient, lock_id\]
+        Retrieve(T::AccountId, T::AccountId, AcuityLockId),
+        /// A sell order is priced by Dutch auction. \[seller, sell_asset_id, start_price, end_price, start, end\]
+        SellAuction(
+            T::AccountId,
+            AcuityAssetId,
+            u128,
+            u128,
+            MomentOf<T, I>,
+            MomentOf<T, I>,
+        ),
+        /// A sell order is no longer priced by Dutch auction. \[seller, sell_asset_id\]
+        SellAuctionCleared(T::AccountId, AcuityAssetId),
+        /// A signed order has been filled. \[maker, nonce, lock_id\]
+        SignedOrderFilled(T::AccountId, u64, AcuityLockId),
+        /// All nonces up to and including a nonce have been cancelled. \[maker, nonce\]
+        NoncesCancelled(T::AccountId, u64),
+        /// A buyer has requested quotes. \[request_id, requester, sell_asset_id, buy_asset_id, value, expiry\]
+        QuoteRequested(
+            u64,
+            T::AccountId,
+            AcuityAssetId,
+            AcuityAssetId,
+            BalanceOf<T, I>,
+            MomentOf<T, I>,
+        ),
+        /// A market maker has quoted a price. \[request_id, maker, price, expiry\]
+        QuoteSubmitted(u64, T::AccountId, u128, MomentOf<T, I>),
+        /// A quote has been accepted into a buy lock. \[request_id, maker, lock_id\]
+        QuoteAccepted(u64, T::AccountId, AcuityLockId),
+        /// A quote request and its quotes have been removed. \[request_id\]
+        QuoteRequestRemoved(u64),
+        /// A liquidity provider has registered for an asset. \[provider, asset_id, foreign_address, min_value, max_value\]
+        LiquidityProviderRegistered(
+            T::AccountId,
+            AcuityAssetId,
+            AcuityForeignAddress,
+            BalanceOf<T, I>,
+            BalanceOf<T, I>,
+        ),
+        /// A liquidity provider has deregistered for an asset. \[provider, asset_id\]
+        LiquidityProviderDeregistered(T::AccountId, AcuityAssetId),
+        /// An Ethereum address has been linked to an account. \[account, address\]
+        EthereumAddressLinked(T::AccountId, EthereumAddress),
+        /// An Ethereum address has been unlinked from an account. \[account, address\]
+        EthereumAddressUnlinked(T::AccountId, EthereumAddress),
+        /// Value has been locked for several recipients. \[creator, lock_id, hashed_secret, timeout, value\]
+        SplitLock(
+            T::AccountId,
+            AcuityLockId,
+            AcuityHashedSecret,
+            MomentOf<T, I>,
+            BalanceOf<T, I>,
+        ),
+        /// A split lock has been unlocked and paid to its recipients. \[creator, lock_id, secret\]
+        SplitUnlock(T::AccountId, AcuityLockId, AcuitySecret),
+        /// A recipient has consented to decline a split lock. \[lock_id, recipient\]
+        SplitDeclineConsent(AcuityLockId, T::AccountId),
+        /// A split lock has been declined. \[creator, lock_id\]
+        SplitDecline(T::AccountId, AcuityLockId),
+        /// A split lock has been retrieved by its creator. \[creator, lock_id\]
+        SplitRetrieve(T::AccountId, AcuityLockId),
+        /// Value has been locked behind several hashed secrets. \[creator, recipient, lock_id, timeout, value, threshold\]
+        ThresholdLock(
+            T::AccountId,
+            T::AccountId,
+            AcuityLockId,
+            MomentOf<T, I>,
+            BalanceOf<T, I>,
+            u32,
+        ),
+        /// A claim NFT has been minted for a lock. \[lock_id, recipient, claim_id\]
+        ClaimMinted(AcuityLockId, T::AccountId, u64),
+        /// A claim NFT has been burned as its lock settled. \[lock_id, owner, claim_id\]
+        ClaimBurned(AcuityLockId, Option<T::AccountId>, u64),
+        /// Value has been added to a lock. \[creator, recipient, lock_id, extra, value\]
+        LockToppedUp(
+            T::AccountId,
+            T::AccountId,
+            AcuityLockId,
+            BalanceOf<T, I>,
+            BalanceOf<T, I>,
+        ),
+        /// Value has been locked behind a point. \[creator, recipient, point, timeout, value, lock_id\]
+        PtlcLock(
+            T::AccountId,
+            T::AccountId,
+            AcuityPoint,
+            MomentOf<T, I>,
+            BalanceOf<T, I>,
+            AcuityLockId,
+        ),
+        /// A secret of a threshold lock has been revealed. \[lock_id, hashed_secret, secret\]
+        SecretRevealed(AcuityLockId, AcuityHashedSecret, AcuitySecret),
+        /// An NFT has been locked. \[creator, recipient, hashed_secret, timeout, collection, item, lock_id\]
+        NftLock(
+            T::AccountId,
+            T::AccountId,
+            AcuityHashedSecret,
+            MomentOf<T, I>,
+            T::NftCollectionId,
+            T::NftItemId,
+            AcuityLockId,
+        ),
+        /// An NFT lock has been unlocked. \[creator, recipient, lock_id, secret, collection, item\]
+        NftUnlock(
+            T::AccountId,
+            T::AccountId,
+            AcuityLockId,
+            AcuitySecret,
+            T::NftCollectionId,
+            T::NftItemId,
+        ),
+        /// An NFT lock has been declined. \[creator, recipient, lock_id, collection, item\]
+        NftDecline(
+            T::AccountId,
+            T::AccountId,
+            AcuityLockId,
+            T::NftCollectionId,
+            T::NftItemId,
+        ),
+        /// An NFT lock has been retrieved. \[creator, recipient, lock_id, collection, item\]
+        NftRetrieve(
+            T::AccountId,
+            T::AccountId,
+            AcuityLockId,
+            T::NftCollectionId,
+            T::NftItemId,
+        ),
+        /// The fund pot has been topped up to the existential deposit with newly minted value. \[amount\]
+        FundAccountProvisioned(BalanceOf<T, I>),
+    }
+
+    #[pallet::error]
+    pub enum Error<T, I = ()> {
+        /// Value must not be zero.
+        ZeroValue,
+        /// Value has already been locked with this lockId.
+        LockAlreadyExists,
+        /// No value has already been locked with this lockId.
+        LockDoesNotExist,
+        /// The lock has timed out.
+        LockTimedOut,
+        /// The lock has not timed out.
+        LockNotTimedOut,
+        /// The lock has already been unlocked.
+        LockAlreadyUnlocked,
+        /// The lock has already been declined.
+        LockAlreadyDeclined,
+        /// The lock has already been retrieved.
+        LockAlreadyRetrieved,
+        /// The creator's free balance is less than the value.
+        InsufficientBalance,
+        /// Locking the value would take the creator below the existential deposit.
+        WouldKillCreator,
+        /// The fund pot holds less than the value of the lock.
+        PotUnderfunded,
+        /// The value would leave the recipient below the existential deposit.
+        RecipientBelowExistentialDeposit,
+        /// The auction price must not increase and the window must not be empty.
+        InvalidAuction,
+        /// No auction has been set for this sell asset.
+        AuctionDoesNotExist,
+        /// The sell price is below the current auction price.
+        SellPriceBelowAuction,
+        /// The signed order has expired.
+        OrderExpired,
+        /// The signed order nonce has been cancelled.
+        OrderNonceCancelled,
+        /// The signed order nonce has already been used.
+        OrderNonceUsed,
+        /// The signed order was not signed by its maker.
+        InvalidOrderSignature,
+        /// No quote request exists with this id.
+        QuoteRequestDoesNotExist,
+        /// The quote request has expired.
+        QuoteRequestExpired,
+        /// The caller did not make the quote request.
+        NotQuoteRequester,
+        /// The request already has the maximum number of quotes.
+        TooManyQuotes,
+        /// The maker has not quoted on this request.
+        QuoteDoesNotExist,
+        /// The quote has expired.
+        QuoteExpired,
+        /// The sell price is not the quoted price.
+        QuotePriceMismatch,
+        /// The minimum swap size is greater than the maximum.
+        InvalidSwapSize,
+        /// The account is not registered as a liquidity provider for this asset.
+        ProviderNotRegistered,
+        /// The recipient is not registered as a liquidity provider for this asset.
+        RecipientNotRegistered,
+        /// The value is outside the recipient's swap size range.
+        ValueOutsideSwapSize,
+        /// The signature was not made by the Ethereum address over the account id.
+        InvalidEthereumSignature,
+        /// The Ethereum address is already linked to an account.
+        EthereumAddressAlreadyLinked,
+        /// No Ethereum address is linked to the account.
+        EthereumAddressNotLinked,
+        /// The batch contains no locks.
+        EmptyBatch,
+        /// The creator has no timed out locks.
+        NoExpiredLocks,
+        /// The caller is not the recipient of any open lock for the secret.
+        NoLocksForSecret,
+        /// A split lock has no shares.
+        NoShares,
+        /// A recipient has more than one share.
+        DuplicateShareRecipient,
+        /// The decline consent threshold is zero or more than the number of recipients.
+        InvalidDeclineConsent,
+        /// The caller is not a recipient of the split lock.
+        NotShareRecipient,
+        /// The recipient has already consented to decline.
+        AlreadyConsented,
+        /// The secret does not match the hashed secret of the lock.
+        WrongSecret,
+        /// A threshold lock has no hashed secrets.
+        NoHashedSecrets,
+        /// A hashed secret is listed more than once.
+        DuplicateHashedSecret,
+        /// The threshold is zero or more than the number of hashed secrets.
+        InvalidThreshold,
+        /// The secret has already been revealed.
+        SecretAlreadyRevealed,
+        /// The scalar is zero or not less than the secp256k1 group order.
+        InvalidScalar,
+        /// A claim NFT has already been minted for the lock.
+        ClaimAlreadyMinted,
+        /// No claim NFT has been minted for the lock.
+        ClaimDoesNotExist,
+        /// The recipient has sold the claim NFT of the lock.
+        NotClaimOwner,
+        /// The sender does not own the NFT.
+        NotNftOwner,
+        /// The account has too many open quote requests.
+        TooManyQuoteRequests,
+        /// Claim NFTs cannot be locked.
+        ClaimNftNotLockable,
+        /// A share is less than the existential deposit.
+        ShareBelowExistentialDeposit,
+    }
+
+    #[pallet::storage]
+    #[pallet::getter(fn lock_id_value)]
+    pub(super) type LockIdValue<T: Config<I>, I: 'static = ()> =
+        StorageMap<_, Identity, AcuityLockId, BalanceOf<T, I>>;
+
+    #[pallet::storage]
+    #[pallet::getter(fn lock_state)]
+    pub(super) type LockStates<T: Config<I>, I: 'static = ()> =
+        StorageMap<_, Identity, AcuityLockId, LockState>;
+
+    /// The fields of each open lock.
+    #[pallet::storage]
+    #[pallet::getter(fn lock_info)]
+    pub(super) type LockInfos<T: Config<I>, I: 'static = ()> =
+        StorageMap<_, Identity, AcuityLockId, LockInfo<T::AccountId, MomentOf<T, I>>>;
+
+    /// Open locks by creator, ordered by timeout.
+    ///
+    /// Locks created before the index was introduced are not in it and must be retrieved one by
+    /// one.
+    #[pallet::storage]
+    pub(super) type CreatorLocks<T: Config<I>, I: 'static = ()> =
+        StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Identity, CreatorLockKey, ()>;
+
+    /// Claim NFTs minted for open locks.
+    #[pallet::storage]
+    #[pallet::getter(fn lock_claim)]
+    pub(super) type LockClaims<T: Config<I>, I: 'static = ()> =
+        StorageMap<_, Identity, AcuityLockId, u64>;
+
+    /// Assets sold to the creators of open buy locks.
+    #[pallet::storage]
+    #[pallet::getter(fn buy_lock_asset)]
+    pub(super) type BuyLockAssets<T: Config<I>, I: 'static = ()> =
+        StorageMap<_, Identity, AcuityLockId, AcuityAssetId>;
+
+    #[pallet::storage]
+    pub(super) type NextClaimId<T: Config<I>, I: 'static = ()> = StorageValue<_, u64, ValueQuery>;
+
+    /// NFTs held by open NFT locks.
+    #[pallet::storage]
+    #[pallet::getter(fn nft_lock)]
+    pub(super) type NftLocks<T: Config<I>, I: 'static = ()> =
+        StorageMap<_, Identity, AcuityLockId, (T::NftCollectionId, T::NftItemId)>;
+
+    /// Open threshold locks.
+    #[pallet::storage]
+    #[pallet::getter(fn threshold_lock)]
+    pub(super) type ThresholdLocks<T: Config<I>, I: 'static = ()> =
+        StorageMap<_, Identity, AcuityLockId, ThresholdLockOf<T, I>>;
+
+    /// Open split locks.
+    #[pallet::storage]
+    #[pallet::getter(fn split_lock)]
+    pub(super) type SplitLocks<T: Config<I>, I: 'static = ()> =
+        StorageMap<_, Identity, AcuityLockId, SplitLockOf<T, I>>;
+
+    /// Open locks by hashed secret.
+    #[pallet::storage]
+    pub(super) type HashedSecretLocks<T: Config<I>, I: 'static = ()> =
+        StorageDoubleMap<_, Blake2_128Concat, AcuityHashedSecret, Identity, AcuityLockId, ()>;
+
+    /// Progress of backfilling the states of locks created before lock states were retained.
+    #[pallet::storage]
+    pub(super) type LockStateBackfillCursor<T: Config<I>, I: 'static = ()> =
+        StorageValue<_, MigrationCursor>;
+
+    /// Settled locks whose state is pruned at a block.
+    #[pallet::storage]
+    pub(super) type LockStateExpiry<T: Config<I>, I: 'static = ()> =
+        StorageDoubleMap<_, Twox64Concat, BlockNumberFor<T>, Identity, AcuityLockId, ()>;
+
+    /// First block whose settled lock states may not all have been pruned.
+    #[pallet::storage]
+    pub(super) type LockStatePruneCursor<T: Config<I>, I: 'static = ()> =
+        StorageValue<_, BlockNumberFor<T>>;
+
+    #[pallet::storage]
+    #[pallet::getter(fn sell_auction)]
+    pub(super) type SellAuction<T: Config<I>, I: 'static = ()> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        Blake2_128Concat,
+        AcuityAssetId,
+        DutchAuction<MomentOf<T, I>>,
+    >;
+
+    /// Signed order nonces below this value have been cancelled.
+    #[pallet::storage]
+    #[pallet::getter(fn maker_min_nonce)]
+    pub(super) type MakerMinNonce<T: Config<I>, I: 'static = ()> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, u64, ValueQuery>;
+
+    /// Signed order nonces that have been filled.
+    #[pallet::storage]
+    pub(super) type MakerNonceUsed<T: Config<I>, I: 'static = ()> =
+        StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Twox64Concat, u64, ()>;
+
+    #[pallet::storage]
+    pub(super) type NextQuoteRequestId<T: Config<I>, I: 'static = ()> =
+        StorageValue<_, u64, ValueQuery>;
+
+    #[pallet::storage]
+    #[pallet::getter(fn quote_request)]
+    pub(super) type QuoteRequests<T: Config<I>, I: 'static = ()> =
+        StorageMap<_, Twox64Concat, u64, QuoteRequestOf<T, I>>;
+
+    /// Number of open quote requests of each account.
+    #[pallet::storage]
+    #[pallet::getter(fn quote_request_count)]
+    pub(super) type QuoteRequestCount<T: Config<I>, I: 'static = ()> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, u32, ValueQuery>;
+
+    /// Quote requests that are pruned at a block.
+    #[pallet::storage]
+    pub(super) type QuoteRequestExpiry<T: Config<I>, I: 'static = ()> =
+        StorageDoubleMap<_, Twox64Concat, BlockNumberFor<T>, Twox64Concat, u64, ()>;
+
+    /// First block whose quote requests may not all have been pruned.
+    #[pallet::storage]
+    pub(super) type QuoteRequestPruneCursor<T: Config<I>, I: 'static = ()> =
+        StorageValue<_, BlockNumberFor<T>>;
+
+    #[pallet::storage]
+    #[pallet::getter(fn quote)]
+    pub(super) type Quotes<T: Config<I>, I: 'static = ()> = StorageDoubleMap<
+        _,
+        Twox64Concat,
+        u64,
+        Blake2_128Concat,
+        T::AccountId,
+        Quote<MomentOf<T, I>>,
+    >;
+
+    #[pallet::storage]
+    #[pallet::getter(fn liquidity_provider)]
+    pub(super) type LiquidityProviders<T: Config<I>, I: 'static = ()> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        AcuityAssetId,
+        Blake2_128Concat,
+        T::AccountId,
+        LiquidityProviderOf<T, I>,
+    >;
+
+    #[pallet::storage]
+    #[pallet::getter(fn account_ethereum_address)]
+    pub(super) type AccountEthereumAddress<T: Config<I>, I: 'static = ()> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, EthereumAddress>;
+
+    #[pallet::storage]
+    #[pallet::getter(fn ethereum_address_account)]
+    pub(super) type EthereumAddressAccount<T: Config<I>, I: 'static = ()> =
+        StorageMap<_, Identity, EthereumAddress, T::AccountId>;
+
+    impl<T: Config<I>, I: 'static> Pallet<T, I> {
+        /// The account ID of the fund pot.
+        ///
+        /// This actually does computation. If you need to keep using it, then make sure you cache the
+        /// value and only call this once.
+        pub fn fund_account_id() -> T::AccountId {
+            T::PalletId::get().into_account_truncating()
+        }
+
+        /// The message a maker signs to authorize an order on this chain and pallet instance.
+        pub fn signed_order_message(order: &SignedOrder<T::AccountId, MomentOf<T, I>>) -> Vec<u8> {
+            (
+                b"acuity-order",
+                T::ChainDomain::get(),
+                T::PalletId::get(),
+                order,
+            )
+                .encode()
+        }
+
+        pub fn get_lock_id(
+            sender: T::AccountId,
+            recipient: T::AccountId,
+            hashed_secret: AcuityHashedSecret,
+            timeout: MomentOf<T, I>,
+        ) -> AcuityLockId {
+            match T::LockIdScheme::get() {
+                super::LockIdScheme::Blake2Scale => {
+                    let mut lock_id = AcuityLockId::default();
+                    lock_id.0.copy_from_slice(&blake2_256(
+                        &[
+                            T::ChainDomain::get().encode(),
+                            T::PalletId::get().encode(),
+                            sender.encode(),
+                            recipient.encode(),
+                            hashed_secret.encode(),
+                            timeout.encode(),
+                        ]
+                        .concat(),
+                    ));
+                    lock_id
+                }
+                super::LockIdScheme::KeccakAbiPacked => Self::get_lock_id_abi_packed(
+                    &sender.encode(),
+                    &recipient.encode(),
+                    hashed_secret,
+                    timeout.unique_saturated_into(),
+                ),
+            }
+        }
+
+        /// Top up the fund pot to the existential deposit so that it is never reaped.
+        ///
+        /// The top up is minted, so total issuance grows by at most one existential deposit per
+        /// instance. `FundAccountProvisioned` records the amount.
+        pub fn provision_fund_account() {
+            let fund_account_id = Self::fund_account_id();
+            let minimum_balance = T::Currency::minimum_balance();
+            let free_balance = T::Currency::free_balance(&fund_account_id);
+            if free_balance < minimum_balance {
+                let amount = minimum_balance - free_balance;
+                let _ = T::Currency::deposit_creating(&fund_account_id, amount);
+                Self::deposit_event(Event::FundAccountProvisioned(amount));
+            }
+        }
+
+        /// Check the pallet's storage invariants.
+        #[cfg(any(feature = "try-runtime", test))]
+        pub fn do_try_state() -> Result<(), sp_runtime::TryRuntimeError> {
+            // Every lock holds value and has not been settled.
+            let mut outstanding = BalanceOf::<T, I>::zero();
+            for (lock_id, value) in <LockIdValue<T, I>>::iter() {
+                ensure!(!value.is_zero(), "Lock has zero value.");
+                ensure!(
+                    matches!(
+                        <LockStates<T, I>>::get(lock_id),
+                        None | Some(LockState::Open)
+                    ),
+                    "Lock with value has been settled."
+                );
+                outstanding = outstanding.saturating_add(value);
+            }
+            // Anyone can transfer into the pot, so it may hold more than this.
+            ensure!(
+                T::Currency::free_balance(&Self::fund_account_id())
+                    >= outstanding.saturating_add(T::Currency::minimum_balance()),
+                "Fund pot does not cover outstanding locks and the existential deposit."
+            );
+            // Every indexed lock is open and indexed by its creator.
+            for (lock_id, info) in <LockInfos<T, I>>::iter() {
+                ensure!(
+                    <LockIdValue<T, I>>::contains_key(lock_id),
+                    "Indexed lock has no value."
+                );
+                ensure!(
+                    <CreatorLocks<T, I>>::contains_key(
+                        &info.creator,
+                        Self::creator_lock_key(info.timeout, lock_id)
+                    ),
+                    "Lock is not indexed by its creator."
+                );
+            }
+            ensure!(
+                <CreatorLocks<T, I>>::iter().count() == <LockInfos<T, I>>::iter().count(),
+                "Creator lock index does not match the indexed locks."
+            );
+            for (hashed_secret, lock_id, ()) in <HashedSecretLocks<T, I>>::iter() {
+                ensure!(
+                    <LockInfos<T, I>>::get(lock_id).map(|info| info.hashed_secret)
+                        == Some(hashed_secret),
+                    "Lock is indexed by the wrong hashed secret."
+                );
+            }
+            ensure!(
+                <HashedSecretLocks<T, I>>::iter().count() == <LockInfos<T, I>>::iter().count(),
+                "Hashed secret index does not match the indexed locks."
+            );
+            // Every claim NFT belongs to an open lock and exists.
+            for (lock_id, claim_id) in <LockClaims<T, I>>::iter() {
+                ensure!(
+                    <LockIdValue<T, I>>::contains_key(lock_id),
+                    "Claim NFT belongs to a settled lock."
+                );
+                ensure!(
+                    claim_id < <NextClaimId<T, I>>::get(),
+                    "Claim NFT id has not been issued."
+                );
+                ensure!(
+                    T::Nfts::owner(&T::ClaimCollection::get(), &claim_id.into()).is_some(),
+                    "Claim NFT does not exist."
+                );
+            }
+            // Every buy lock asset belongs to an open lock.
+            for (lock_id, _) in <BuyLockAssets<T, I>>::iter() {
+                ensure!(
+                    <LockIdValue<T, I>>::contains_key(lock_id),
+                    "Buy lock asset belongs to a settled lock."
+                );
+            }
+            // Every NFT lock is open and its NFT is held by the pot.
+            for (lock_id, (collection, item)) in <NftLocks<T, I>>::iter() {
+                ensure!(
+                    <LockStates<T, I>>::get(lock_id) == Some(LockState::Open),
+                    "NFT lock has been settled."
+                );
+                ensure!(
+                    T::Nfts::owner(&collection, &item) == Some(Self::fund_account_id()),
+                    "Locked NFT is not held by the pot."
+                );
+            }
+            // Every threshold lock is open and still needs a secret.
+            for (lock_id, lock) in <ThresholdLocks<T, I>>::iter() {
+                ensure!(
+                    <LockIdValue<T, I>>::contains_key(lock_id),
+                    "Threshold lock has no value."
+                );
+                ensure!(
+                    (lock
+                        .secrets
+                        .iter()
+                        .filter(|(_, secret)| secret.is_some())
+                        .count() as u32)
+                        < lock.threshold,
+                    "Threshold lock has met its threshold."
+                );
+            }
+            // Every split lock is open and holds the sum of its shares.
+            for (lock_id, lock) in <SplitLocks<T, I>>::iter() {
+                let value = lock
+                    .shares
+                    .iter()
+                    .fold(BalanceOf::<T, I>::zero(), |total, (_, share)| {
+                        total.saturating_add(*share)
+                    });
+                ensure!(
+                    <LockIdValue<T, I>>::get(lock_id) == Some(value),
+                    "Split lock does not hold the sum of its shares."
+                );
+            }
+            // Every retained state of a settled lock is due to be pruned.
+            for (_, lock_id, ()) in <LockStateExpiry<T, I>>::iter() {
+                ensure!(
+                    !matches!(
+                        <LockStates<T, I>>::get(lock_id),
+                        None | Some(LockState::Open)
+                    ),
+                    "Lock state due to be pruned is not settled."
+                );
+            }
+            // Every quote belongs to a request that counts it.
+            for (request_id, request) in <QuoteRequests<T, I>>::iter() {
+                ensure!(
+                    request_id < <NextQuoteRequestId<T, I>>::get(),
+                    "Quote request id has not been issued."
+                );
+                ensure!(
+                    <Quotes<T, I>>::iter_prefix(request_id).count() as u32 == request.quote_count,
+                    "Quote request does not count its quotes."
+                );
+                ensure!(
+                    request.quote_count <= T::MaxQuotesPerRequest::get(),
+                    "Quote request has too many quotes."
+                );
+            }
+            for (request_id, _, _) in <Quotes<T, I>>::iter() {
+                ensure!(
+                    <QuoteRequests<T, I>>::contains_key(request_id),
+                    "Quote does not belong to a request."
+                );
+            }
+            // Every quote request is counted against its requester and will be pruned.
+            for (requester, count) in <QuoteRequestCount<T, I>>::iter() {
+                ensure!(
+                    <QuoteRequests<T, I>>::iter_values()
+                        .filter(|request| request.requester == requester)
+                        .count() as u32
+                        == count,
+                    "Quote request count does not match the requests."
+                );
+                ensure!(
+                    count <= T::MaxQuoteRequestsPerAccount::get(),
+                    "Account has too many quote requests."
+                );
+            }
+            ensure!(
+                <QuoteRequestCount<T, I>>::iter_values().sum::<u32>() as usize
+                    == <QuoteRequests<T, I>>::iter().count(),
+                "Quote request count does not match the requests."
+            );
+            for request_id in <QuoteRequests<T, I>>::iter_keys() {
+                ensure!(
+                    <QuoteRequestExpiry<T, I>>::iter().any(|(_, id, ())| id == request_id),
+                    "Quote request is not due to be pruned."
+                );
+            }
+            // Ethereum address links are bidirectional.
+            for (account, address) in <AccountEthereumAddress<T, I>>::iter() {
+                ensure!(
+                    <EthereumAddressAccount<T, I>>::get(address) == Some(account),
+                    "Ethereum address is not linked back to its account."
+                );
+            }
+            ensure!(
+                <AccountEthereumAddress<T, I>>::iter().count()
+                    == <EthereumAddressAccount<T, I>>::iter().count(),
+                "Ethereum address links are not bidirectional."
+            );
+            Ok(())
+        }
+
+        /// Move value from a creator into the fund pot.
+        fn transfer_to_pot(creator: &T::AccountId, value: BalanceOf<T, I>) -> DispatchResult {
+            let free_balance = T::Currency::free_balance(creator);
+            ensure!(free_balance >= value, Error::<T, I>::InsufficientBalance);
+            ensure!(
+                free_balance - value >= T::Currency::minimum_balance(),
+                Error::<T, I>::WouldKillCreator
+            );
+            T::Currency::transfer(creator, &Self::fund_account_id(), value, KeepAlive)
+        }
+
+        /// Move value out of the fund pot.
+        fn transfer_from_pot(dest: &T::AccountId, value: BalanceOf<T, I>) -> DispatchResult {
+            let fund_account_id = Self::fund_account_id();
+            // The pot keeps the existential deposit in reserve.
+            ensure!(
+                T::Currency::free_balance(&fund_account_id)
+                    .saturating_sub(T::Currency::minimum_balance())
+                    >= value,
+                Error::<T, I>::PotUnderfunded
+            );
+            ensure!(
+                T::Currency::free_balance(dest).saturating_add(value)
+                    >= T::Currency::minimum_balance(),
+                Error::<T, I>::RecipientBelowExistentialDeposit
+            );
+            T::Currency::transfer(&fund_account_id, dest, value, KeepAlive)
+        }
+
+        /// Lock id of an NFT lock, which does not depend on `LockIdScheme`.
+        pub fn get_nft_lock_id(
+            sender: &T::AccountId,
+            recipient: &T::AccountId,
+            hashed_secret: AcuityHashedSecret,
+            timeout: MomentOf<T, I>,
+        ) -> AcuityLockId {
+            let mut lock_id = AcuityLockId::default();
+            lock_id.0.copy_from_slice(&blake2_256(
+                &[
+                    T::ChainDomain::get().encode(),
+                    T::PalletId::get().encode(),
+                    b"nft".encode(),
+                    sender.encode(),
+                    recipient.encode(),
+                    hashed_secret.encode(),
+                    timeout.encode(),
+                ]
+                .concat(),
+            ));
+            lock_id
+        }
+
+        /// Lock id of a point time-lock, which does not depend on `LockIdScheme`.
+        pub fn get_ptlc_lock_id(
+            sender: &T::AccountId,
+            recipient: &T::AccountId,
+            point: AcuityPoint,
+            timeout: MomentOf<T, I>,
+        ) -> AcuityLockId {
+            let mut lock_id = AcuityLockId::default();
+            lock_id.0.copy_from_slice(&blake2_256(
+                &[
+                    T::ChainDomain::get().encode(),
+                    T::PalletId::get().encode(),
+                    b"ptlc".encode(),
+                    sender.encode(),
+                    recipient.encode(),
+                    point.encode(),
+                    timeout.encode(),
+                ]
+                .concat(),
+            ));
+            lock_id
+        }
+
+        /// Lock id of a threshold lock, which does not depend on `LockIdScheme`.
+        pub fn get_threshold_lock_id(
+            creator: &T::AccountId,
+            recipient: &T::AccountId,
+            hashed_secrets: &[AcuityHashedSecret],
+            threshold: u32,
+            timeout: MomentOf<T, I>,
+        ) -> AcuityLockId {
+            let mut lock_id = AcuityLockId::default();
+            lock_id.0.copy_from_slice(&blake2_256(
+                &[
+                    T::ChainDomain::get().encode(),
+                    T::PalletId::get().encode(),
+                    b"threshold".encode(),
+                    creator.encode(),
+                    recipient.encode(),
+                    hashed_secrets.encode(),
+                    threshold.encode(),
+                    timeout.encode(),
+                ]
+                .concat(),
+            ));
+            lock_id
+        }
+
+        /// Progress of an open threshold lock.
+        pub fn threshold_lock_progress(lock_id: AcuityLockId) -> Option<ThresholdLockProgress> {
+            let lock = <ThresholdLocks<T, I>>::get(lock_id)?;
+            Some(ThresholdLockProgress {
+                secrets: lock.secrets.into_inner(),
+                threshold: lock.threshold,
+            })
+        }
+
+        /// Lock id of a split lock, which does not depend on `LockIdScheme`.
+        pub fn get_split_lock_id(
+            creator: &T::AccountId,
+            hashed_secret: AcuityHashedSecret,
+            timeout: MomentOf<T, I>,
+            shares: &[(T::AccountId, BalanceOf<T, I>)],
+        ) -> AcuityLockId {
+            let mut lock_id = AcuityLockId::default();
+            lock_id.0.copy_from_slice(&blake2_256(
+                &[
+                    T::ChainDomain::get().encode(),
+                    T::PalletId::get().encode(),
+                    b"split".encode(),
+                    creator.encode(),
+                    shares.encode(),
+                    hashed_secret.encode(),
+                    timeout.encode(),
+                ]
+                .concat(),
+            ));
+            lock_id
+        }
+
+        /// Weight of examining up to `max` locks of a creator and retrieving the timed out ones.
+        pub fn retrieve_all_expired_weight(max: u32) -> Weight {
+            T::DbWeight::get()
+                .reads(2u64.saturating_mul(max.into()))
+                .saturating_add(Weight::from_parts(50_000_000, 0).saturating_mul(max.into()))
+        }
+
+        /// Weight of unlocking up to `max` locks with a hashed secret for the caller.
+        pub fn unlock_by_secret_weight(max: u32) -> Weight {
+            T::DbWeight::get()
+                .reads(2u64.saturating_mul(max.into()))
+                .saturating_add(Weight::from_parts(50_000_000, 0).saturating_mul(max.into()))
+        }
+
+        /// Key of a lock in the creator index, which sorts by big-endian timeout.
+        fn creator_lock_key(timeout: MomentOf<T, I>, lock_id: AcuityLockId) -> CreatorLockKey {
+            let timeout: u128 = timeout.unique_saturated_into();
+            (timeout.to_be_bytes(), lock_id)
+        }
+
+        /// Open a lock and index it by its creator.
+        fn insert_lock(
+            lock_id: AcuityLockId,
+            creator: &T::AccountId,
+            recipient: &T::AccountId,
+            hashed_secret: AcuityHashedSecret,
+            timeout: MomentOf<T, I>,
+            value: BalanceOf<T, I>,
+        ) {
+            <LockIdValue<T, I>>::insert(lock_id, value);
+            <LockStates<T, I>>::insert(lock_id, LockState::Open);
+            <LockInfos<T, I>>::insert(
+                lock_id,
+                LockInfo {
+                    creator: creator.clone(),
+                    recipient: recipient.clone(),
+                    hashed_secret,
+                    timeout,
+                },
+            );
+            <CreatorLocks<T, I>>::insert(creator, Self::creator_lock_key(timeout, lock_id), ());
+            <HashedSecretLocks<T, I>>::insert(hashed_secret, lock_id, ());
+        }
+
+        /// Drain entries due by block `n`, starting at the block `cursor`, until
+        /// `MAX_PRUNED_PER_BLOCK` entries and blocks have been visited.
+        ///
+        /// `drain` prunes up to `max` entries due at a block and returns how many it pruned. The
+        /// block to start from next time is returned with the number of blocks and entries visited.
+        fn prune_due(
+            n: BlockNumberFor<T>,
+            cursor: Option<BlockNumberFor<T>>,
+            mut drain: impl FnMut(BlockNumberFor<T>, usize) -> u32,
+        ) -> (BlockNumberFor<T>, u32, u32) {
+            let mut block = cursor.unwrap_or(n);
+            let mut blocks = 0;
+            let mut pruned = 0;
+            while block <= n && blocks + pruned < MAX_PRUNED_PER_BLOCK {
+                blocks += 1;
+                let max = MAX_PRUNED_PER_BLOCK - blocks - pruned;
+                let count = drain(block, max as usize);
+                pruned += count;
+                // The block may have more entries due.
+                if count == max {
+                    break;
+                }
+                block = block.saturating_add(1u32.into());
+            }
+            (block, blocks, pruned)
+        }
+
+        /// Delete a lock and retain its final state.
+        fn settle_lock(lock_id: AcuityLockId, state: LockState) {
+            <LockIdValue<T, I>>::remove(lock_id);
+            if let Some(info) = <LockInfos<T, I>>::take(lock_id) {
+                <CreatorLocks<T, I>>::remove(
+                    &info.creator,
+                    Self::creator_lock_key(info.timeout, lock_id),
+                );
+                <HashedSecretLocks<T, I>>::remove(info.hashed_secret, lock_id);
+            }
+            <BuyLockAssets<T, I>>::remove(lock_id);
+            <LockStates<T, I>>::insert(lock_id, state);
+            let expiry = <frame_system::Pallet<T>>::block_number()
+                .saturating_add(T::LockStateRetention::get().max(1u32.into()));
+            <LockStateExpiry<T, I>>::insert(expiry, lock_id, ());
+        }
+
+        /// Burn the claim NFT of a lock, if there is one, and return who owned it.
+        fn take_claim(lock_id: AcuityLockId) -> Result<Option<T::AccountId>, DispatchError> {
+            let claim_id = match <LockClaims<T, I>>::take(lock_id) {
+                Some(claim_id) => claim_id,
+                None => return Ok(None),
+            };
+            let collection = T::ClaimCollection::get();
+            let owner = T::Nfts::owner(&collection, &claim_id.into());
+            T::Nfts::burn(&collection, &claim_id.into(), None)?;
+            Self::deposit_event(Event::ClaimBurned(lock_id, owner.clone(), claim_id));
+            Ok(owner)
+        }
+
+        /// Why there is no value locked with a lock id.
+        fn missing_lock_error(lock_id: AcuityLockId) -> Error<T, I> {
+            match <LockStates<T, I>>::get(lock_id) {
+                Some(LockState::Unlocked { .. }) => Error::<T, I>::LockAlreadyUnlocked,
+                Some(LockState::Declined) => Error::<T, I>::LockAlreadyDeclined,
+                Some(LockState::Retrieved) => Error::<T, I>::LockAlreadyRetrieved,
+                _ => Error::<T, I>::LockDoesNotExist,
+            }
+        }
+
+        /// The moment from which a lock with this timeout can no longer be unlocked.
+        pub fn unlock_deadline(timeout: MomentOf<T, I>) -> MomentOf<T, I> {
+            timeout.saturating_sub(T::UnlockCutoff::get())
+        }
+
+        /// The moment from which a lock with this timeout can be retrieved.
+        pub fn refund_from(timeout: MomentOf<T, I>) -> MomentOf<T, I> {
+            timeout.saturating_add(T::RefundDelay::get())
+        }
+
+        /// Lock id as derived before lock ids were domain separated.
+        pub fn get_legacy_lock_id(
+            sender: T::AccountId,
+            recipient: T::AccountId,
+            hashed_secret: AcuityHashedSecret,
+            timeout: MomentOf<T, I>,
+        ) -> AcuityLockId {
+            let mut lock_id = AcuityLockId::default();
+            lock_id.0.copy_from_slice(&blake2_256(
+                &[
+                    sender.encode(),
+                    recipient.encode(),
+                    hashed_secret.encode(),
+                    timeout.encode(),
+                ]
                 .concat(),
             ));
             lock_id
         }
+
+        /// Lock id of an existing lock.
+        ///
+        /// Locks created before lock ids were domain separated remain addressable by their legacy
+        /// lock id until they are settled.
+        fn get_existing_lock_id(
+            sender: T::AccountId,
+            recipient: T::AccountId,
+            hashed_secret: AcuityHashedSecret,
+            timeout: MomentOf<T, I>,
+        ) -> AcuityLockId {
+            let lock_id =
+                Self::get_lock_id(sender.clone(), recipient.clone(), hashed_secret, timeout);
+            if T::LockIdScheme::get() != super::LockIdScheme::Blake2Scale {
+                return lock_id;
+            }
+            let legacy_lock_id =
+                Self::get_legacy_lock_id(sender, recipient, hashed_secret, timeout);
+            // An open lock takes precedence over the retained state of a settled one.
+            if <LockIdValue<T, I>>::contains_key(lock_id) {
+                lock_id
+            } else if <LockIdValue<T, I>>::contains_key(legacy_lock_id) {
+                legacy_lock_id
+            } else if <LockStates<T, I>>::contains_key(lock_id)
+                || !<LockStates<T, I>>::contains_key(legacy_lock_id)
+            {
+                lock_id
+            } else {
+                legacy_lock_id
+            }
+        }
+
+        /// Lock id as derived by the Acuity EVM contracts.
+        ///
+        /// `sender` and `recipient` are packed as their raw bytes, so an `AccountId20` packs
+        /// exactly like a Solidity `address`. `timeout` is packed as a `uint256`. The preimage is
+        /// not domain separated so that it matches the contracts byte for byte.
+        pub fn get_lock_id_abi_packed(
+            sender: &[u8],
+            recipient: &[u8],
+            hashed_secret: AcuityHashedSecret,
+            timeout: u128,
+        ) -> AcuityLockId {
+            let mut packed_timeout = [0u8; 32];
+            packed_timeout[16..].copy_from_slice(&timeout.to_be_bytes());
+            let mut lock_id = AcuityLockId::default();
+            lock_id.0.copy_from_slice(&keccak_256(
+                &[sender, recipient, &hashed_secret.0[..], &packed_timeout[..]].concat(),
+            ));
+            lock_id
+        }
+
+        fn do_lock_buy(
+            creator: T::AccountId,
+            recipient: T::AccountId,
+            hashed_secret: AcuityHashedSecret,
+            timeout: MomentOf<T, I>,
+            value: BalanceOf<T, I>,
+            sell_asset_id: AcuityAssetId,
+            sell_price: u128,
+        ) -> Result<AcuityLockId, DispatchError> {
+            // Ensure the lock can be created.
+            let lock_id = Self::check_lock_buy(
+                &creator,
+                &recipient,
+                hashed_secret,
+                timeout,
+                value,
+                sell_asset_id,
+                sell_price,
+            )?;
+
+            //----------------------------------------
+
+            // Move the value from the sender to the pallet.
+            Self::transfer_to_pot(&creator, value)?;
+            // Move value into buy lock.
+            Self::insert_lock(lock_id, &creator, &recipient, hashed_secret, timeout, value);
+            <BuyLockAssets<T, I>>::insert(lock_id, sell_asset_id);
+            // Log info.
+            Self::deposit_event(Event::LockBuy(
+                creator,
+                recipient,
+                hashed_secret,
+                timeout,
+                value,
+                lock_id,
+                sell_asset_id,
+                sell_price,
+            ));
+            Ok(lock_id)
+        }
+
+        /// Checks that a lock can be created and returns its id.
+        fn check_new_lock(
+            creator: &T::AccountId,
+            recipient: &T::AccountId,
+            hashed_secret: AcuityHashedSecret,
+            timeout: MomentOf<T, I>,
+            value: BalanceOf<T, I>,
+        ) -> Result<AcuityLockId, DispatchError> {
+            // Ensure value is nonzero.
+            frame_support::ensure!(!value.is_zero(), Error::<T, I>::ZeroValue);
+            // Calculate lock_id.
+            let lock_id =
+                Self::get_lock_id(creator.clone(), recipient.clone(), hashed_secret, timeout);
+            // Ensure lock_id is not already in use.
+            ensure!(
+                !LockIdValue::<T, I>::contains_key(lock_id)
+                    && !LockStates::<T, I>::contains_key(lock_id),
+                Error::<T, I>::LockAlreadyExists
+            );
+            // Ensure the lock would not shadow an open lock with a legacy lock id.
+            ensure!(
+                T::LockIdScheme::get() != super::LockIdScheme::Blake2Scale
+                    || !LockIdValue::<T, I>::contains_key(Self::get_legacy_lock_id(
+                        creator.clone(),
+                        recipient.clone(),
+                        hashed_secret,
+                        timeout
+                    )),
+                Error::<T, I>::LockAlreadyExists
+            );
+            Ok(lock_id)
+        }
+
+        /// Checks that a buy lock can be created and returns its id.
+        fn check_lock_buy(
+            creator: &T::AccountId,
+            recipient: &T::AccountId,
+            hashed_secret: AcuityHashedSecret,
+            timeout: MomentOf<T, I>,
+            value: BalanceOf<T, I>,
+            sell_asset_id: AcuityAssetId,
+            sell_price: u128,
+        ) -> Result<AcuityLockId, DispatchError> {
+            let lock_id = Self::check_new_lock(creator, recipient, hashed_secret, timeout, value)?;
+            // Ensure the price meets the seller's auction price.
+            if let Some(auction_price) = Self::sell_auction_price(recipient, sell_asset_id) {
+                ensure!(
+                    sell_price >= auction_price,
+                    Error::<T, I>::SellPriceBelowAuction
+                );
+            }
+            // Ensure the recipient provides liquidity of this size.
+            Self::check_swap_size(recipient, sell_asset_id, value)?;
+            Ok(lock_id)
+        }
+
+        /// Checks that the recipient of a buy lock provides liquidity of its size.
+        fn check_swap_size(
+            recipient: &T::AccountId,
+            sell_asset_id: AcuityAssetId,
+            value: BalanceOf<T, I>,
+        ) -> DispatchResult {
+            match <LiquidityProviders<T, I>>::get(sell_asset_id, recipient) {
+                Some(provider) => ensure!(
+                    value >= provider.min_value && value <= provider.max_value,
+                    Error::<T, I>::ValueOutsideSwapSize
+                ),
+                None => ensure!(
+                    !T::RequireRegisteredRecipient::get(),
+                    Error::<T, I>::RecipientNotRegistered
+                ),
+            }
+            Ok(())
+        }
+
+        /// Delete a quote request and its quotes, returning whether it existed.
+        fn remove_quote_request(request_id: u64) -> bool {
+            let request = match <QuoteRequests<T, I>>::take(request_id) {
+                Some(request) => request,
+                None => return false,
+            };
+            <QuoteRequestCount<T, I>>::mutate_exists(&request.requester, |count| {
+                *count = count
+                    .map(|count| count.saturating_sub(1))
+                    .filter(|count| *count > 0)
+            });
+            let _ = <Quotes<T, I>>::clear_prefix(request_id, T::MaxQuotesPerRequest::get(), None);
+            true
+        }
+
+        /// Constructs the message that Ethereum RPC's `personal_sign` and `eth_sign` would sign.
+        pub fn ethereum_signable_message(what: &[u8]) -> Vec<u8> {
+            let mut l = what.len();
+            let mut rev = Vec::new();
+            while l > 0 {
+                rev.push(b'0' + (l % 10) as u8);
+                l /= 10;
+            }
+            let mut v = b"\x19Ethereum Signed Message:\n".to_vec();
+            v.extend(rev.into_iter().rev());
+            v.extend_from_slice(what);
+            v
+        }
+
+        /// Attempts to recover the Ethereum address from a message signature signed by using
+        /// the Ethereum RPC's `personal_sign` and `eth_sign`.
+        pub fn eth_recover(signature: &EthereumSignature, what: &[u8]) -> Option<EthereumAddress> {
+            let msg = keccak_256(&Self::ethereum_signable_message(what));
+            let mut address = EthereumAddress::default();
+            address.0.copy_from_slice(
+                &keccak_256(&sp_io::crypto::secp256k1_ecdsa_recover(&signature.0, &msg).ok()?[..])
+                    [12..],
+            );
+            Some(address)
+        }
+
+        /// All liquidity providers registered for an asset.
+        pub fn liquidity_providers(
+            asset_id: AcuityAssetId,
+        ) -> Vec<(T::AccountId, LiquidityProviderOf<T, I>)> {
+            <LiquidityProviders<T, I>>::iter_prefix(asset_id).collect()
+        }
+
+        /// The current price of a seller's Dutch auction, if there is one.
+        pub fn sell_auction_price(
+            seller: &T::AccountId,
+            sell_asset_id: AcuityAssetId,
+        ) -> Option<u128> {
+            let auction = <SellAuction<T, I>>::get(seller, sell_asset_id)?;
+            let now = T::TimeProvider::now();
+            if now <= auction.start {
+                return Some(auction.start_price);
+            }
+            if now >= auction.end {
+                return Some(auction.end_price);
+            }
+            let elapsed: u128 = (now - auction.start).unique_saturated_into();
+            let window: u128 = (auction.end - auction.start).unique_saturated_into();
+            // Round the decay down so the seller never receives less than the linear price.
+            let decay = multiply_by_rational_with_rounding(
+                auction.start_price - auction.end_price,
+                elapsed,
+                window,
+                Rounding::Down,
+            )?;
+            Some(auction.start_price - decay)
+        }
     }
 }
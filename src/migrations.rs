@@ -0,0 +1,134 @@
+//! Storage migrations for the atomic swap pallet.
+
+use super::*;
+use frame_support::{
+    migrations::VersionedMigration,
+    traits::{Get, OnRuntimeUpgrade},
+    weights::Weight,
+};
+#[cfg(feature = "try-runtime")]
+use sp_runtime::TryRuntimeError;
+
+/// Provisions the fund pot with the existential deposit so that payouts can keep it alive.
+///
+/// The existential deposit is minted into the pot, which increases total issuance.
+pub struct ProvisionFundAccount<T, I = ()>(PhantomData<(T, I)>);
+
+impl<T: Config<I>, I: 'static> OnRuntimeUpgrade for ProvisionFundAccount<T, I> {
+    fn on_runtime_upgrade() -> Weight {
+        Pallet::<T, I>::provision_fund_account();
+        T::DbWeight::get().reads_writes(1, 1)
+    }
+
+    #[cfg(feature = "try-runtime")]
+    fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+        Ok(T::Currency::free_balance(&Pallet::<T, I>::fund_account_id()).encode())
+    }
+
+    #[cfg(feature = "try-runtime")]
+    fn post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+        let pre_balance = BalanceOf::<T, I>::decode(&mut &state[..])
+            .map_err(|_| "Fund pot balance could not be decoded.")?;
+        let balance = T::Currency::free_balance(&Pallet::<T, I>::fund_account_id());
+        frame_support::ensure!(balance >= pre_balance, "Fund pot balance decreased.");
+        frame_support::ensure!(
+            balance >= T::Currency::minimum_balance(),
+            "Fund pot is below the existential deposit."
+        );
+        Pallet::<T, I>::do_try_state()
+    }
+}
+
+pub mod v1 {
+    use super::*;
+
+    /// Provisions the fund pot when upgrading from storage version 0.
+    pub type MigrateV0ToV1<T, I = ()> = VersionedMigration<
+        0,
+        1,
+        ProvisionFundAccount<T, I>,
+        Pallet<T, I>,
+        <T as frame_system::Config>::DbWeight,
+    >;
+}
+
+pub mod v2 {
+    use super::*;
+
+    /// Maximum number of locks backfilled in a block.
+    pub const MAX_LOCKS_PER_STEP: u32 = 256;
+
+    /// Starts backfilling `LockState::Open` for locks created before lock states were retained.
+    ///
+    /// The backfill itself runs over as many blocks as it needs in `on_idle`.
+    pub struct StartBackfillLockStates<T, I = ()>(PhantomData<(T, I)>);
+
+    impl<T: Config<I>, I: 'static> OnRuntimeUpgrade for StartBackfillLockStates<T, I> {
+        fn on_runtime_upgrade() -> Weight {
+            <LockStateBackfillCursor<T, I>>::put(MigrationCursor::Start);
+            T::DbWeight::get().writes(1)
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(_state: Vec<u8>) -> Result<(), TryRuntimeError> {
+            frame_support::ensure!(
+                <LockStateBackfillCursor<T, I>>::exists(),
+                "Lock state backfill has not started."
+            );
+            Ok(())
+        }
+    }
+
+    /// Starts the lock state backfill when upgrading from storage version 1.
+    pub type MigrateV1ToV2<T, I = ()> = VersionedMigration<
+        1,
+        2,
+        StartBackfillLockStates<T, I>,
+        Pallet<T, I>,
+        <T as frame_system::Config>::DbWeight,
+    >;
+
+    /// Backfills the states of up to `limit` locks and returns how many were visited.
+    pub fn step<T: Config<I>, I: 'static>(limit: u32) -> u32 {
+        let mut lock_ids = match <LockStateBackfillCursor<T, I>>::get() {
+            Some(MigrationCursor::Start) => <LockIdValue<T, I>>::iter_keys(),
+            Some(MigrationCursor::After(lock_id)) => {
+                <LockIdValue<T, I>>::iter_keys_from_key(lock_id)
+            }
+            None => return 0,
+        };
+        let mut count = 0;
+        while count < limit {
+            match lock_ids.next() {
+                Some(lock_id) => {
+                    if !<LockStates<T, I>>::contains_key(lock_id) {
+                        <LockStates<T, I>>::insert(lock_id, LockState::Open);
+                    }
+                    <LockStateBackfillCursor<T, I>>::put(MigrationCursor::After(lock_id));
+                    count += 1;
+                }
+                None => {
+                    <LockStateBackfillCursor<T, I>>::kill();
+                    break;
+                }
+            }
+        }
+        count
+    }
+
+    /// Backfills as many lock states as fit in the remaining weight of a block.
+    pub fn on_idle<T: Config<I>, I: 'static>(remaining_weight: Weight) -> Weight {
+        let base_weight = T::DbWeight::get().reads_writes(1, 1);
+        if remaining_weight.any_lt(base_weight) || !<LockStateBackfillCursor<T, I>>::exists() {
+            return T::DbWeight::get().reads(1);
+        }
+        let lock_weight = T::DbWeight::get().reads_writes(2, 2);
+        let limit = remaining_weight
+            .saturating_sub(base_weight)
+            .checked_div_per_component(&lock_weight)
+            .unwrap_or(u64::MAX)
+            .min(MAX_LOCKS_PER_STEP.into()) as u32;
+        let count = step::<T, I>(limit);
+        base_weight.saturating_add(lock_weight.saturating_mul(count.into()))
+    }
+}
@@ -2,16 +2,17 @@
 
 use super::*;
 use crate as pallet_acuity_atomic_swap;
+use frame_support::instances::Instance2;
 use frame_support::traits::{ConstU16, ConstU64};
 use frame_support::{parameter_types, PalletId};
 use frame_system as system;
 use sp_core::H256;
 use sp_runtime::{
-    testing::Header,
+    testing::{TestSignature, UintAuthorityId},
     traits::{BlakeTwo256, IdentityLookup},
+    BuildStorage,
 };
 
-type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
 type Block = frame_system::mocking::MockBlock<Test>;
 
 /// Type used for expressing timestamp.
@@ -22,15 +23,13 @@ pub type Balance = u128;
 
 // Configure a mock runtime to test the pallet.
 frame_support::construct_runtime!(
-    pub enum Test where
-        Block = Block,
-        NodeBlock = Block,
-        UncheckedExtrinsic = UncheckedExtrinsic,
+    pub enum Test
     {
         System: frame_system,
-        Timestamp: pallet_timestamp,
         Balances: pallet_balances,
+        Uniques: pallet_uniques,
         AcuityAtomicSwap: pallet_acuity_atomic_swap,
+        AcuityAtomicSwapStable: pallet_acuity_atomic_swap::<Instance2>,
     }
 );
 
@@ -46,13 +45,12 @@ impl system::Config for Test {
     type DbWeight = ();
     type RuntimeOrigin = RuntimeOrigin;
     type RuntimeCall = RuntimeCall;
-    type Index = u64;
-    type BlockNumber = u64;
+    type Nonce = u64;
     type Hash = H256;
     type Hashing = BlakeTwo256;
     type AccountId = u64;
     type Lookup = IdentityLookup<Self::AccountId>;
-    type Header = Header;
+    type Block = Block;
     type RuntimeEvent = RuntimeEvent;
     type BlockHashCount = ConstU64<250>;
     type Version = ();
@@ -66,15 +64,23 @@ impl system::Config for Test {
     type MaxConsumers = frame_support::traits::ConstU32<16>;
 }
 
-impl pallet_timestamp::Config for Test {
+parameter_types! {
+    pub static Now: Moment = 0;
+}
+
+/// Clock that tests set through `Now`.
+pub struct MockTime;
+
+impl Time for MockTime {
     type Moment = Moment;
-    type OnTimestampSet = ();
-    type MinimumPeriod = ();
-    type WeightInfo = pallet_timestamp::weights::SubstrateWeight<Test>;
+
+    fn now() -> Moment {
+        Now::get()
+    }
 }
 
 parameter_types! {
-    pub const ExistentialDeposit: u64 = 1;
+    pub static ExistentialDeposit: Balance = 1;
 }
 
 impl pallet_balances::Config for Test {
@@ -89,30 +95,139 @@ impl pallet_balances::Config for Test {
     type WeightInfo = pallet_balances::weights::SubstrateWeight<Test>;
     type FreezeIdentifier = ();
     type MaxFreezes = ();
-    type HoldIdentifier = ();
+    type RuntimeHoldReason = ();
+    type RuntimeFreezeReason = ();
     type MaxHolds = ();
 }
 
+impl pallet_uniques::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type CollectionId = u32;
+    type ItemId = u64;
+    type Currency = Balances;
+    type ForceOrigin = frame_system::EnsureRoot<u64>;
+    type CreateOrigin =
+        frame_support::traits::AsEnsureOriginWithArg<frame_system::EnsureSigned<u64>>;
+    type Locker = ();
+    type CollectionDeposit = frame_support::traits::ConstU128<0>;
+    type ItemDeposit = frame_support::traits::ConstU128<0>;
+    type MetadataDepositBase = frame_support::traits::ConstU128<0>;
+    type AttributeDepositBase = frame_support::traits::ConstU128<0>;
+    type DepositPerByte = frame_support::traits::ConstU128<0>;
+    type StringLimit = frame_support::traits::ConstU32<50>;
+    type KeyLimit = frame_support::traits::ConstU32<50>;
+    type ValueLimit = frame_support::traits::ConstU32<50>;
+    type WeightInfo = ();
+}
+
+/// Collection that claim NFTs are minted in.
+pub const CLAIM_COLLECTION: u32 = 0;
+
 parameter_types! {
     pub const AtomicSwapPalletId: PalletId = PalletId(*b"py/trsry");
+    pub static RequireRegisteredRecipient: bool = false;
+    pub static MockLockIdScheme: LockIdScheme = LockIdScheme::Blake2Scale;
+    pub static ChainDomain: [u8; 32] = [1; 32];
+    pub static UnlockCutoff: Moment = 0;
+    pub static RefundDelay: Moment = 0;
+    pub static LockStateRetention: u64 = 10;
 }
 
 impl Config for Test {
     type RuntimeEvent = RuntimeEvent;
     type PalletId = AtomicSwapPalletId;
     type Currency = Balances;
+    type TimeProvider = MockTime;
+    type UnlockCutoff = UnlockCutoff;
+    type RefundDelay = RefundDelay;
+    type OffchainSignature = TestSignature;
+    type OffchainPublic = UintAuthorityId;
+    type MaxQuotesPerRequest = frame_support::traits::ConstU32<2>;
+    type MaxQuoteRequestsPerAccount = frame_support::traits::ConstU32<2>;
+    type QuoteRequestLifetime = ConstU64<20>;
+    type RequireRegisteredRecipient = RequireRegisteredRecipient;
+    type LockIdScheme = MockLockIdScheme;
+    type ChainDomain = ChainDomain;
+    type LockStateRetention = LockStateRetention;
+    type MaxLocksPerBatch = frame_support::traits::ConstU32<3>;
+    type MaxShares = frame_support::traits::ConstU32<3>;
+    type MaxHashedSecrets = frame_support::traits::ConstU32<3>;
+    type Nfts = Uniques;
+    type ClaimCollection = frame_support::traits::ConstU32<CLAIM_COLLECTION>;
+    type NftCollectionId = u32;
+    type NftItemId = u64;
+}
+
+parameter_types! {
+    pub const StableAtomicSwapPalletId: PalletId = PalletId(*b"py/stbsw");
+}
+
+impl Config<Instance2> for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type PalletId = StableAtomicSwapPalletId;
+    type Currency = Balances;
+    type TimeProvider = MockTime;
+    type UnlockCutoff = UnlockCutoff;
+    type RefundDelay = RefundDelay;
+    type OffchainSignature = TestSignature;
+    type OffchainPublic = UintAuthorityId;
+    type MaxQuotesPerRequest = frame_support::traits::ConstU32<2>;
+    type MaxQuoteRequestsPerAccount = frame_support::traits::ConstU32<2>;
+    type QuoteRequestLifetime = ConstU64<20>;
+    type RequireRegisteredRecipient = RequireRegisteredRecipient;
+    type LockIdScheme = MockLockIdScheme;
+    type ChainDomain = ChainDomain;
+    type LockStateRetention = ConstU64<10>;
+    type MaxLocksPerBatch = frame_support::traits::ConstU32<3>;
+    type MaxShares = frame_support::traits::ConstU32<3>;
+    type MaxHashedSecrets = frame_support::traits::ConstU32<3>;
+    // The stable instance runs without an NFT pallet.
+    type Nfts = NoNfts<u32, u64>;
+    type ClaimCollection = frame_support::traits::ConstU32<0>;
+    type NftCollectionId = u32;
+    type NftItemId = u64;
 }
 
 const A: u64 = 1;
 const B: u64 = 2;
 
 pub fn new_test_ext() -> sp_io::TestExternalities {
-    let mut t = frame_system::GenesisConfig::default()
-        .build_storage::<Test>()
+    new_test_ext_with_genesis(Default::default())
+}
+
+/// Build genesis storage with the given swap state for the default instance.
+pub fn new_test_ext_with_genesis(
+    swap_genesis: pallet_acuity_atomic_swap::GenesisConfig<Test>,
+) -> sp_io::TestExternalities {
+    let mut t = frame_system::GenesisConfig::<Test>::default()
+        .build_storage()
         .unwrap();
     let genesis = pallet_balances::GenesisConfig::<Test> {
         balances: vec![(A, 100), (B, 100)],
     };
     genesis.assimilate_storage(&mut t).unwrap();
-    t.into()
+    swap_genesis.assimilate_storage(&mut t).unwrap();
+    pallet_acuity_atomic_swap::GenesisConfig::<Test, Instance2>::default()
+        .assimilate_storage(&mut t)
+        .unwrap();
+    let mut ext: sp_io::TestExternalities = t.into();
+    ext.execute_with(|| {
+        Uniques::force_create(
+            RuntimeOrigin::root(),
+            CLAIM_COLLECTION,
+            AcuityAtomicSwap::fund_account_id(),
+            true,
+        )
+        .unwrap();
+    });
+    ext
+}
+
+/// Run a test and then check the invariants of both pallet instances.
+pub fn run_test(test: impl FnOnce()) {
+    new_test_ext().execute_with(|| {
+        test();
+        AcuityAtomicSwap::do_try_state().unwrap();
+        AcuityAtomicSwapStable::do_try_state().unwrap();
+    });
 }
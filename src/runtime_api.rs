@@ -0,0 +1,30 @@
+//! Runtime API definition for the atomic swap pallet.
+
+use crate::{AcuityAssetId, AcuityLockId, LiquidityProvider, LockState, ThresholdLockProgress};
+use codec::Codec;
+use sp_std::vec::Vec;
+
+sp_api::decl_runtime_apis! {
+    pub trait AcuityAtomicSwapApi<AccountId, Balance, Moment>
+    where
+        AccountId: Codec,
+        Balance: Codec,
+        Moment: Codec,
+    {
+        /// The current Dutch auction price of a seller's sell order, if there is one.
+        fn sell_auction_price(seller: AccountId, sell_asset_id: AcuityAssetId) -> Option<u128>;
+
+        /// All liquidity providers registered for an asset.
+        fn liquidity_providers(asset_id: AcuityAssetId) -> Vec<(AccountId, LiquidityProvider<Balance>)>;
+
+        /// The moments from which a lock with this timeout can no longer be unlocked and can be
+        /// retrieved.
+        fn lock_windows(timeout: Moment) -> (Moment, Moment);
+
+        /// The state of a lock, if it is open or was settled within the retention period.
+        fn lock_state(lock_id: AcuityLockId) -> Option<LockState>;
+
+        /// The secrets revealed so far for an open threshold lock.
+        fn threshold_lock_progress(lock_id: AcuityLockId) -> Option<ThresholdLockProgress>;
+    }
+}
@@ -0,0 +1,98 @@
+//! Minimal secp256k1 scalar arithmetic for verifying point time-locks.
+//!
+//! The runtime only exposes public key recovery, so `scalar·G` is computed by recovering the
+//! public key of a signature over a zero message with `R = G`:
+//! `Q = r⁻¹(s·R − 0·G) = r⁻¹·s·G`, so choosing `r = Gx` and `s = scalar·Gx mod n` gives
+//! `Q = scalar·G`.
+
+/// Order of the secp256k1 group, as big-endian 64-bit limbs.
+const N: [u64; 4] = [
+    0xFFFF_FFFF_FFFF_FFFF,
+    0xFFFF_FFFF_FFFF_FFFE,
+    0xBAAE_DCE6_AF48_A03B,
+    0xBFD2_5E8C_D036_4141,
+];
+
+/// X coordinate of the secp256k1 generator, as big-endian 64-bit limbs.
+const GX: [u64; 4] = [
+    0x79BE_667E_F9DC_BBAC,
+    0x55A0_6295_CE87_0B07,
+    0x029B_FCDB_2DCE_28D9,
+    0x59F2_815B_16F8_1798,
+];
+
+fn from_bytes(bytes: &[u8; 32]) -> [u64; 4] {
+    let mut limbs = [0u64; 4];
+    for (limb, chunk) in limbs.iter_mut().zip(bytes.chunks_exact(8)) {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(chunk);
+        *limb = u64::from_be_bytes(buf);
+    }
+    limbs
+}
+
+fn to_bytes(limbs: &[u64; 4]) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    for (chunk, limb) in bytes.chunks_exact_mut(8).zip(limbs.iter()) {
+        chunk.copy_from_slice(&limb.to_be_bytes());
+    }
+    bytes
+}
+
+/// `a - b`, wrapping.
+fn sub(a: &[u64; 4], b: &[u64; 4]) -> [u64; 4] {
+    let mut result = [0u64; 4];
+    let mut borrow = false;
+    for i in (0..4).rev() {
+        let (d, b1) = a[i].overflowing_sub(b[i]);
+        let (d, b2) = d.overflowing_sub(borrow as u64);
+        result[i] = d;
+        borrow = b1 || b2;
+    }
+    result
+}
+
+/// `a + b mod n` for `a, b < n`.
+fn add_mod(a: &[u64; 4], b: &[u64; 4]) -> [u64; 4] {
+    let mut result = [0u64; 4];
+    let mut carry = false;
+    for i in (0..4).rev() {
+        let (s, c1) = a[i].overflowing_add(b[i]);
+        let (s, c2) = s.overflowing_add(carry as u64);
+        result[i] = s;
+        carry = c1 || c2;
+    }
+    if carry || result >= N {
+        result = sub(&result, &N);
+    }
+    result
+}
+
+/// `a · b mod n` for `a, b < n`.
+fn mul_mod(a: &[u64; 4], b: &[u64; 4]) -> [u64; 4] {
+    let mut result = [0u64; 4];
+    for limb in b.iter() {
+        for bit in (0..64).rev() {
+            result = add_mod(&result, &result);
+            if (limb >> bit) & 1 == 1 {
+                result = add_mod(&result, a);
+            }
+        }
+    }
+    result
+}
+
+/// The compressed point `scalar·G`, or `None` if the scalar is zero or not less than the group
+/// order.
+pub fn point_from_scalar(scalar: &[u8; 32]) -> Option<[u8; 33]> {
+    let k = from_bytes(scalar);
+    if k == [0u64; 4] || k >= N {
+        return None;
+    }
+    let mut sig = [0u8; 65];
+    sig[..32].copy_from_slice(&to_bytes(&GX));
+    sig[32..64].copy_from_slice(&to_bytes(&mul_mod(&k, &GX)));
+    // The y coordinate of G is even.
+    sig[64] = 0;
+    sp_io::crypto::secp256k1_ecdsa_recover_compressed(&sig, &[0u8; 32]).ok()
+}
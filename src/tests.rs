@@ -1,20 +1,27 @@
 use super::*;
-use crate::{mock::*, Error};
-use frame_support::{assert_noop, assert_ok};
+use crate::{mock::*, Error, GenesisConfig};
+use frame_support::{
+    assert_noop, assert_ok,
+    instances::Instance2,
+    traits::{Currency, GetStorageVersion, Hooks, OnRuntimeUpgrade, StorageVersion},
+    weights::Weight,
+};
 use sp_io::hashing::keccak_256;
+use sp_runtime::{testing::TestSignature, TokenError};
 
 const A: u64 = 1;
 const B: u64 = 2;
+const C: u64 = 3;
 
 #[test]
 fn lock_buy_control_lock_zero_value() {
-    new_test_ext().execute_with(|| {
+    run_test(|| {
         let secret = AcuitySecret::default();
         let mut hashed_secret = AcuityHashedSecret::default();
         hashed_secret
             .0
             .copy_from_slice(&keccak_256(&secret.encode()));
-        let now = <pallet_timestamp::Pallet<Test>>::get();
+        let now = Now::get();
         let value = 50;
         assert_ok!(AcuityAtomicSwap::lock_buy(
             RuntimeOrigin::signed(B),
@@ -30,13 +37,13 @@ fn lock_buy_control_lock_zero_value() {
 
 #[test]
 fn lock_buy_fail_lock_zero_value() {
-    new_test_ext().execute_with(|| {
+    run_test(|| {
         let secret = AcuitySecret::default();
         let mut hashed_secret = AcuityHashedSecret::default();
         hashed_secret
             .0
             .copy_from_slice(&keccak_256(&secret.encode()));
-        let now = <pallet_timestamp::Pallet<Test>>::get();
+        let now = Now::get();
         let value = 0;
         assert_noop!(
             AcuityAtomicSwap::lock_buy(
@@ -55,13 +62,13 @@ fn lock_buy_fail_lock_zero_value() {
 
 #[test]
 fn lock_buy_control_lock_already_exists() {
-    new_test_ext().execute_with(|| {
+    run_test(|| {
         let secret = AcuitySecret::default();
         let mut hashed_secret = AcuityHashedSecret::default();
         hashed_secret
             .0
             .copy_from_slice(&keccak_256(&secret.encode()));
-        let now = <pallet_timestamp::Pallet<Test>>::get();
+        let now = Now::get();
         assert_ok!(AcuityAtomicSwap::lock_buy(
             RuntimeOrigin::signed(B),
             A,
@@ -76,7 +83,7 @@ fn lock_buy_control_lock_already_exists() {
             A,
             hashed_secret,
             now + 1001,
-            50,
+            49,
             AcuityAssetId::default(),
             5
         ));
@@ -85,13 +92,13 @@ fn lock_buy_control_lock_already_exists() {
 
 #[test]
 fn lock_buy_fail_lock_already_exists() {
-    new_test_ext().execute_with(|| {
+    run_test(|| {
         let secret = AcuitySecret::default();
         let mut hashed_secret = AcuityHashedSecret::default();
         hashed_secret
             .0
             .copy_from_slice(&keccak_256(&secret.encode()));
-        let now = <pallet_timestamp::Pallet<Test>>::get();
+        let now = Now::get();
         assert_ok!(AcuityAtomicSwap::lock_buy(
             RuntimeOrigin::signed(B),
             A,
@@ -118,13 +125,13 @@ fn lock_buy_fail_lock_already_exists() {
 
 #[test]
 fn lock_buy() {
-    new_test_ext().execute_with(|| {
+    run_test(|| {
         let secret = AcuitySecret::default();
         let mut hashed_secret = AcuityHashedSecret::default();
         hashed_secret
             .0
             .copy_from_slice(&keccak_256(&secret.encode()));
-        let timeout = <pallet_timestamp::Pallet<Test>>::get() + 1000;
+        let timeout = Now::get() + 1000;
         let value = 50;
 
         assert_ok!(AcuityAtomicSwap::lock_buy(
@@ -139,7 +146,7 @@ fn lock_buy() {
         assert_eq!(Balances::free_balance(B), 50);
         assert_eq!(
             Balances::free_balance(AcuityAtomicSwap::fund_account_id()),
-            50
+            51
         );
 
         let lock_id = AcuityAtomicSwap::get_lock_id(B, A, hashed_secret, timeout);
@@ -149,13 +156,13 @@ fn lock_buy() {
 
 #[test]
 fn lock_sell_control_lock_zero_value() {
-    new_test_ext().execute_with(|| {
+    run_test(|| {
         let secret = AcuitySecret::default();
         let mut hashed_secret = AcuityHashedSecret::default();
         hashed_secret
             .0
             .copy_from_slice(&keccak_256(&secret.encode()));
-        let now = <pallet_timestamp::Pallet<Test>>::get();
+        let now = Now::get();
         let value = 50;
         assert_ok!(AcuityAtomicSwap::lock_sell(
             RuntimeOrigin::signed(B),
@@ -171,13 +178,13 @@ fn lock_sell_control_lock_zero_value() {
 
 #[test]
 fn lock_sell_fail_lock_zero_value() {
-    new_test_ext().execute_with(|| {
+    run_test(|| {
         let secret = AcuitySecret::default();
         let mut hashed_secret = AcuityHashedSecret::default();
         hashed_secret
             .0
             .copy_from_slice(&keccak_256(&secret.encode()));
-        let now = <pallet_timestamp::Pallet<Test>>::get();
+        let now = Now::get();
         let value = 0;
         assert_noop!(
             AcuityAtomicSwap::lock_sell(
@@ -196,13 +203,13 @@ fn lock_sell_fail_lock_zero_value() {
 
 #[test]
 fn lock_sell_control_lock_already_exists() {
-    new_test_ext().execute_with(|| {
+    run_test(|| {
         let secret = AcuitySecret::default();
         let mut hashed_secret = AcuityHashedSecret::default();
         hashed_secret
             .0
             .copy_from_slice(&keccak_256(&secret.encode()));
-        let now = <pallet_timestamp::Pallet<Test>>::get();
+        let now = Now::get();
         assert_ok!(AcuityAtomicSwap::lock_sell(
             RuntimeOrigin::signed(B),
             A,
@@ -217,7 +224,7 @@ fn lock_sell_control_lock_already_exists() {
             A,
             hashed_secret,
             now + 1001,
-            50,
+            49,
             AcuityAssetId::default(),
             AcuityLockId::default()
         ));
@@ -226,13 +233,13 @@ fn lock_sell_control_lock_already_exists() {
 
 #[test]
 fn lock_sell_fail_lock_already_exists() {
-    new_test_ext().execute_with(|| {
+    run_test(|| {
         let secret = AcuitySecret::default();
         let mut hashed_secret = AcuityHashedSecret::default();
         hashed_secret
             .0
             .copy_from_slice(&keccak_256(&secret.encode()));
-        let now = <pallet_timestamp::Pallet<Test>>::get();
+        let now = Now::get();
         assert_ok!(AcuityAtomicSwap::lock_sell(
             RuntimeOrigin::signed(B),
             A,
@@ -259,13 +266,13 @@ fn lock_sell_fail_lock_already_exists() {
 
 #[test]
 fn lock_sell() {
-    new_test_ext().execute_with(|| {
+    run_test(|| {
         let secret = AcuitySecret::default();
         let mut hashed_secret = AcuityHashedSecret::default();
         hashed_secret
             .0
             .copy_from_slice(&keccak_256(&secret.encode()));
-        let timeout = <pallet_timestamp::Pallet<Test>>::get() + 1000;
+        let timeout = Now::get() + 1000;
         let value = 50;
         assert_ok!(AcuityAtomicSwap::lock_sell(
             RuntimeOrigin::signed(B),
@@ -279,7 +286,7 @@ fn lock_sell() {
         assert_eq!(Balances::free_balance(B), 50);
         assert_eq!(
             Balances::free_balance(AcuityAtomicSwap::fund_account_id()),
-            50
+            51
         );
 
         let lock_id = AcuityAtomicSwap::get_lock_id(B, A, hashed_secret, timeout);
@@ -289,13 +296,13 @@ fn lock_sell() {
 
 #[test]
 fn decline_control_not_exist() {
-    new_test_ext().execute_with(|| {
+    run_test(|| {
         let secret = AcuitySecret::default();
         let mut hashed_secret = AcuityHashedSecret::default();
         hashed_secret
             .0
             .copy_from_slice(&keccak_256(&secret.encode()));
-        let timeout = <pallet_timestamp::Pallet<Test>>::get() + 1000;
+        let timeout = Now::get() + 1000;
         let value = 50;
 
         assert_ok!(AcuityAtomicSwap::lock_buy(
@@ -318,13 +325,13 @@ fn decline_control_not_exist() {
 
 #[test]
 fn decline_fail_not_exist() {
-    new_test_ext().execute_with(|| {
+    run_test(|| {
         let secret = AcuitySecret::default();
         let mut hashed_secret = AcuityHashedSecret::default();
         hashed_secret
             .0
             .copy_from_slice(&keccak_256(&secret.encode()));
-        let timeout = <pallet_timestamp::Pallet<Test>>::get() + 1000;
+        let timeout = Now::get() + 1000;
 
         assert_noop!(
             AcuityAtomicSwap::decline(RuntimeOrigin::signed(A), B, hashed_secret, timeout),
@@ -335,13 +342,13 @@ fn decline_fail_not_exist() {
 
 #[test]
 fn decline() {
-    new_test_ext().execute_with(|| {
+    run_test(|| {
         let secret = AcuitySecret::default();
         let mut hashed_secret = AcuityHashedSecret::default();
         hashed_secret
             .0
             .copy_from_slice(&keccak_256(&secret.encode()));
-        let timeout = <pallet_timestamp::Pallet<Test>>::get() + 1000;
+        let timeout = Now::get() + 1000;
         let value = 50;
         let lock_id = AcuityAtomicSwap::get_lock_id(B, A, hashed_secret, timeout);
 
@@ -357,7 +364,7 @@ fn decline() {
         assert_eq!(Balances::free_balance(B), 50);
         assert_eq!(
             Balances::free_balance(AcuityAtomicSwap::fund_account_id()),
-            50
+            51
         );
         assert_eq!(AcuityAtomicSwap::lock_id_value(lock_id), Some(value));
 
@@ -370,7 +377,7 @@ fn decline() {
         assert_eq!(Balances::free_balance(B), 100);
         assert_eq!(
             Balances::free_balance(AcuityAtomicSwap::fund_account_id()),
-            0
+            1
         );
         assert_eq!(AcuityAtomicSwap::lock_id_value(lock_id), None);
     });
@@ -378,13 +385,13 @@ fn decline() {
 
 #[test]
 fn unlock_control_timed_out() {
-    new_test_ext().execute_with(|| {
+    run_test(|| {
         let secret = AcuitySecret::default();
         let mut hashed_secret = AcuityHashedSecret::default();
         hashed_secret
             .0
             .copy_from_slice(&keccak_256(&secret.encode()));
-        let timeout = <pallet_timestamp::Pallet<Test>>::get() + 1000;
+        let timeout = Now::get() + 1000;
         let value = 50;
 
         assert_ok!(AcuityAtomicSwap::lock_buy(
@@ -407,13 +414,13 @@ fn unlock_control_timed_out() {
 
 #[test]
 fn unlock_fail_timed_out() {
-    new_test_ext().execute_with(|| {
+    run_test(|| {
         let secret = AcuitySecret::default();
         let mut hashed_secret = AcuityHashedSecret::default();
         hashed_secret
             .0
             .copy_from_slice(&keccak_256(&secret.encode()));
-        let timeout = <pallet_timestamp::Pallet<Test>>::get();
+        let timeout = Now::get();
         let value = 50;
 
         assert_ok!(AcuityAtomicSwap::lock_buy(
@@ -434,13 +441,13 @@ fn unlock_fail_timed_out() {
 
 #[test]
 fn unlock_control_not_exist() {
-    new_test_ext().execute_with(|| {
+    run_test(|| {
         let secret = AcuitySecret::default();
         let mut hashed_secret = AcuityHashedSecret::default();
         hashed_secret
             .0
             .copy_from_slice(&keccak_256(&secret.encode()));
-        let timeout = <pallet_timestamp::Pallet<Test>>::get() + 1000;
+        let timeout = Now::get() + 1000;
         let value = 50;
 
         assert_ok!(AcuityAtomicSwap::lock_buy(
@@ -463,13 +470,13 @@ fn unlock_control_not_exist() {
 
 #[test]
 fn unlock_fail_not_exist() {
-    new_test_ext().execute_with(|| {
+    run_test(|| {
         let secret = AcuitySecret::default();
         let mut hashed_secret = AcuityHashedSecret::default();
         hashed_secret
             .0
             .copy_from_slice(&keccak_256(&secret.encode()));
-        let timeout = <pallet_timestamp::Pallet<Test>>::get() + 1000;
+        let timeout = Now::get() + 1000;
 
         assert_noop!(
             AcuityAtomicSwap::unlock(RuntimeOrigin::signed(A), B, secret, timeout),
@@ -480,13 +487,13 @@ fn unlock_fail_not_exist() {
 
 #[test]
 fn unlock() {
-    new_test_ext().execute_with(|| {
+    run_test(|| {
         let secret = AcuitySecret::default();
         let mut hashed_secret = AcuityHashedSecret::default();
         hashed_secret
             .0
             .copy_from_slice(&keccak_256(&secret.encode()));
-        let timeout = <pallet_timestamp::Pallet<Test>>::get() + 1000;
+        let timeout = Now::get() + 1000;
         let value = 50;
         let lock_id = AcuityAtomicSwap::get_lock_id(B, A, hashed_secret, timeout);
 
@@ -502,7 +509,7 @@ fn unlock() {
         assert_eq!(Balances::free_balance(B), 50);
         assert_eq!(
             Balances::free_balance(AcuityAtomicSwap::fund_account_id()),
-            50
+            51
         );
         assert_eq!(AcuityAtomicSwap::lock_id_value(lock_id), Some(value));
 
@@ -515,7 +522,7 @@ fn unlock() {
         assert_eq!(Balances::free_balance(A), 150);
         assert_eq!(
             Balances::free_balance(AcuityAtomicSwap::fund_account_id()),
-            0
+            1
         );
         assert_eq!(AcuityAtomicSwap::lock_id_value(lock_id), None);
     });
@@ -523,13 +530,13 @@ fn unlock() {
 
 #[test]
 fn retrieve_control_not_timed_out() {
-    new_test_ext().execute_with(|| {
+    run_test(|| {
         let secret = AcuitySecret::default();
         let mut hashed_secret = AcuityHashedSecret::default();
         hashed_secret
             .0
             .copy_from_slice(&keccak_256(&secret.encode()));
-        let timeout = <pallet_timestamp::Pallet<Test>>::get();
+        let timeout = Now::get();
         let value = 50;
 
         assert_ok!(AcuityAtomicSwap::lock_buy(
@@ -552,13 +559,13 @@ fn retrieve_control_not_timed_out() {
 
 #[test]
 fn retrieve_fail_timed_out() {
-    new_test_ext().execute_with(|| {
+    run_test(|| {
         let secret = AcuitySecret::default();
         let mut hashed_secret = AcuityHashedSecret::default();
         hashed_secret
             .0
             .copy_from_slice(&keccak_256(&secret.encode()));
-        let timeout = <pallet_timestamp::Pallet<Test>>::get() + 1000;
+        let timeout = Now::get() + 1000;
         let value = 50;
 
         assert_ok!(AcuityAtomicSwap::lock_buy(
@@ -580,13 +587,13 @@ fn retrieve_fail_timed_out() {
 
 #[test]
 fn retrieve_control_not_exist() {
-    new_test_ext().execute_with(|| {
+    run_test(|| {
         let secret = AcuitySecret::default();
         let mut hashed_secret = AcuityHashedSecret::default();
         hashed_secret
             .0
             .copy_from_slice(&keccak_256(&secret.encode()));
-        let timeout = <pallet_timestamp::Pallet<Test>>::get();
+        let timeout = Now::get();
         let value = 50;
 
         assert_ok!(AcuityAtomicSwap::lock_buy(
@@ -609,13 +616,13 @@ fn retrieve_control_not_exist() {
 
 #[test]
 fn retrieve_fail_not_exist() {
-    new_test_ext().execute_with(|| {
+    run_test(|| {
         let secret = AcuitySecret::default();
         let mut hashed_secret = AcuityHashedSecret::default();
         hashed_secret
             .0
             .copy_from_slice(&keccak_256(&secret.encode()));
-        let timeout = <pallet_timestamp::Pallet<Test>>::get();
+        let timeout = Now::get();
 
         assert_noop!(
             AcuityAtomicSwap::retrieve(RuntimeOrigin::signed(B), A, hashed_secret, timeout),
@@ -626,13 +633,13 @@ fn retrieve_fail_not_exist() {
 
 #[test]
 fn retrieve() {
-    new_test_ext().execute_with(|| {
+    run_test(|| {
         let secret = AcuitySecret::default();
         let mut hashed_secret = AcuityHashedSecret::default();
         hashed_secret
             .0
             .copy_from_slice(&keccak_256(&secret.encode()));
-        let timeout = <pallet_timestamp::Pallet<Test>>::get();
+        let timeout = Now::get();
         let value = 50;
         let lock_id = AcuityAtomicSwap::get_lock_id(B, A, hashed_secret, timeout);
 
@@ -648,7 +655,7 @@ fn retrieve() {
         assert_eq!(Balances::free_balance(B), 50);
         assert_eq!(
             Balances::free_balance(AcuityAtomicSwap::fund_account_id()),
-            50
+            51
         );
         assert_eq!(AcuityAtomicSwap::lock_id_value(lock_id), Some(value));
 
@@ -661,8 +668,3652 @@ fn retrieve() {
         assert_eq!(Balances::free_balance(B), 100);
         assert_eq!(
             Balances::free_balance(AcuityAtomicSwap::fund_account_id()),
-            0
+            1
         );
         assert_eq!(AcuityAtomicSwap::lock_id_value(lock_id), None);
     });
 }
+
+#[test]
+fn set_sell_auction_control_invalid_auction() {
+    run_test(|| {
+        let now = Now::get();
+        assert_ok!(AcuityAtomicSwap::set_sell_auction(
+            RuntimeOrigin::signed(A),
+            AcuityAssetId::default(),
+            100,
+            50,
+            now,
+            now + 1000
+        ));
+    });
+}
+
+#[test]
+fn set_sell_auction_fail_invalid_auction() {
+    run_test(|| {
+        let now = Now::get();
+        assert_noop!(
+            AcuityAtomicSwap::set_sell_auction(
+                RuntimeOrigin::signed(A),
+                AcuityAssetId::default(),
+                50,
+                100,
+                now,
+                now + 1000
+            ),
+            Error::<Test>::InvalidAuction
+        );
+        assert_noop!(
+            AcuityAtomicSwap::set_sell_auction(
+                RuntimeOrigin::signed(A),
+                AcuityAssetId::default(),
+                100,
+                50,
+                now + 1000,
+                now + 1000
+            ),
+            Error::<Test>::InvalidAuction
+        );
+    });
+}
+
+#[test]
+fn clear_sell_auction_fail_not_exist() {
+    run_test(|| {
+        assert_noop!(
+            AcuityAtomicSwap::clear_sell_auction(
+                RuntimeOrigin::signed(A),
+                AcuityAssetId::default()
+            ),
+            Error::<Test>::AuctionDoesNotExist
+        );
+    });
+}
+
+#[test]
+fn sell_auction_price() {
+    run_test(|| {
+        Now::set(1000);
+        assert_eq!(
+            AcuityAtomicSwap::sell_auction_price(&A, AcuityAssetId::default()),
+            None
+        );
+        assert_ok!(AcuityAtomicSwap::set_sell_auction(
+            RuntimeOrigin::signed(A),
+            AcuityAssetId::default(),
+            100,
+            50,
+            2000,
+            3000
+        ));
+        assert_eq!(
+            AcuityAtomicSwap::sell_auction_price(&A, AcuityAssetId::default()),
+            Some(100)
+        );
+        Now::set(2500);
+        assert_eq!(
+            AcuityAtomicSwap::sell_auction_price(&A, AcuityAssetId::default()),
+            Some(75)
+        );
+        Now::set(2999);
+        assert_eq!(
+            AcuityAtomicSwap::sell_auction_price(&A, AcuityAssetId::default()),
+            Some(51)
+        );
+        Now::set(4000);
+        assert_eq!(
+            AcuityAtomicSwap::sell_auction_price(&A, AcuityAssetId::default()),
+            Some(50)
+        );
+        assert_ok!(AcuityAtomicSwap::clear_sell_auction(
+            RuntimeOrigin::signed(A),
+            AcuityAssetId::default()
+        ));
+        assert_eq!(
+            AcuityAtomicSwap::sell_auction_price(&A, AcuityAssetId::default()),
+            None
+        );
+    });
+}
+
+#[test]
+fn lock_buy_control_sell_price_below_auction() {
+    run_test(|| {
+        let secret = AcuitySecret::default();
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&secret.encode()));
+        Now::set(2500);
+        assert_ok!(AcuityAtomicSwap::set_sell_auction(
+            RuntimeOrigin::signed(A),
+            AcuityAssetId::default(),
+            100,
+            50,
+            2000,
+            3000
+        ));
+        assert_ok!(AcuityAtomicSwap::lock_buy(
+            RuntimeOrigin::signed(B),
+            A,
+            hashed_secret,
+            3500,
+            50,
+            AcuityAssetId::default(),
+            75
+        ));
+    });
+}
+
+#[test]
+fn lock_buy_fail_sell_price_below_auction() {
+    run_test(|| {
+        let secret = AcuitySecret::default();
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&secret.encode()));
+        Now::set(2500);
+        assert_ok!(AcuityAtomicSwap::set_sell_auction(
+            RuntimeOrigin::signed(A),
+            AcuityAssetId::default(),
+            100,
+            50,
+            2000,
+            3000
+        ));
+        assert_noop!(
+            AcuityAtomicSwap::lock_buy(
+                RuntimeOrigin::signed(B),
+                A,
+                hashed_secret,
+                3500,
+                50,
+                AcuityAssetId::default(),
+                74
+            ),
+            Error::<Test>::SellPriceBelowAuction
+        );
+    });
+}
+
+#[test]
+fn fill_signed_order_control_invalid_signature() {
+    run_test(|| {
+        let order = SignedOrder {
+            maker: A,
+            sell_asset_id: AcuityAssetId::default(),
+            buy_asset_id: AcuityAssetId::default(),
+            price: 5,
+            expiry: Now::get() + 1000,
+            nonce: 0,
+        };
+        let signature = TestSignature(A, AcuityAtomicSwap::signed_order_message(&order));
+        assert_ok!(AcuityAtomicSwap::fill_signed_order(
+            RuntimeOrigin::signed(B),
+            order,
+            signature,
+            AcuityHashedSecret::default(),
+            Now::get() + 1000,
+            50
+        ));
+    });
+}
+
+#[test]
+fn fill_signed_order_fail_invalid_signature() {
+    run_test(|| {
+        let order = SignedOrder {
+            maker: A,
+            sell_asset_id: AcuityAssetId::default(),
+            buy_asset_id: AcuityAssetId::default(),
+            price: 5,
+            expiry: Now::get() + 1000,
+            nonce: 0,
+        };
+        let signature = TestSignature(B, AcuityAtomicSwap::signed_order_message(&order));
+        assert_noop!(
+            AcuityAtomicSwap::fill_signed_order(
+                RuntimeOrigin::signed(B),
+                order,
+                signature,
+                AcuityHashedSecret::default(),
+                Now::get() + 1000,
+                50
+            ),
+            Error::<Test>::InvalidOrderSignature
+        );
+    });
+}
+
+#[test]
+fn fill_signed_order_fail_expired() {
+    run_test(|| {
+        let mut order = SignedOrder {
+            maker: A,
+            sell_asset_id: AcuityAssetId::default(),
+            buy_asset_id: AcuityAssetId::default(),
+            price: 5,
+            expiry: Now::get() + 1000,
+            nonce: 0,
+        };
+        order.expiry = Now::get();
+        let signature = TestSignature(A, AcuityAtomicSwap::signed_order_message(&order));
+        assert_noop!(
+            AcuityAtomicSwap::fill_signed_order(
+                RuntimeOrigin::signed(B),
+                order,
+                signature,
+                AcuityHashedSecret::default(),
+                Now::get() + 1000,
+                50
+            ),
+            Error::<Test>::OrderExpired
+        );
+    });
+}
+
+#[test]
+fn fill_signed_order_fail_nonce_used() {
+    run_test(|| {
+        let order = SignedOrder {
+            maker: A,
+            sell_asset_id: AcuityAssetId::default(),
+            buy_asset_id: AcuityAssetId::default(),
+            price: 5,
+            expiry: Now::get() + 1000,
+            nonce: 0,
+        };
+        let signature = TestSignature(A, AcuityAtomicSwap::signed_order_message(&order));
+        let timeout = Now::get() + 1000;
+        assert_ok!(AcuityAtomicSwap::fill_signed_order(
+            RuntimeOrigin::signed(B),
+            order.clone(),
+            signature.clone(),
+            AcuityHashedSecret::default(),
+            timeout,
+            50
+        ));
+        assert_noop!(
+            AcuityAtomicSwap::fill_signed_order(
+                RuntimeOrigin::signed(B),
+                order,
+                signature,
+                AcuityHashedSecret::default(),
+                timeout + 1,
+                50
+            ),
+            Error::<Test>::OrderNonceUsed
+        );
+    });
+}
+
+#[test]
+fn fill_signed_order_fail_nonce_cancelled() {
+    run_test(|| {
+        let order = SignedOrder {
+            maker: A,
+            sell_asset_id: AcuityAssetId::default(),
+            buy_asset_id: AcuityAssetId::default(),
+            price: 5,
+            expiry: Now::get() + 1000,
+            nonce: 3,
+        };
+        let signature = TestSignature(A, AcuityAtomicSwap::signed_order_message(&order));
+        assert_ok!(AcuityAtomicSwap::cancel_nonces_up_to(
+            RuntimeOrigin::signed(A),
+            3
+        ));
+        assert_eq!(AcuityAtomicSwap::maker_min_nonce(A), 4);
+        assert_noop!(
+            AcuityAtomicSwap::fill_signed_order(
+                RuntimeOrigin::signed(B),
+                order,
+                signature,
+                AcuityHashedSecret::default(),
+                Now::get() + 1000,
+                50
+            ),
+            Error::<Test>::OrderNonceCancelled
+        );
+        assert_noop!(
+            AcuityAtomicSwap::cancel_nonces_up_to(RuntimeOrigin::signed(A), 2),
+            Error::<Test>::OrderNonceCancelled
+        );
+    });
+}
+
+#[test]
+fn fill_signed_order() {
+    run_test(|| {
+        let order = SignedOrder {
+            maker: A,
+            sell_asset_id: AcuityAssetId::default(),
+            buy_asset_id: AcuityAssetId::default(),
+            price: 5,
+            expiry: Now::get() + 1000,
+            nonce: 0,
+        };
+        let signature = TestSignature(A, AcuityAtomicSwap::signed_order_message(&order));
+        let hashed_secret = AcuityHashedSecret::default();
+        let timeout = Now::get() + 1000;
+        let value = 50;
+
+        assert_ok!(AcuityAtomicSwap::fill_signed_order(
+            RuntimeOrigin::signed(B),
+            order,
+            signature,
+            hashed_secret,
+            timeout,
+            value
+        ));
+        assert_eq!(Balances::free_balance(B), 50);
+        assert_eq!(
+            Balances::free_balance(AcuityAtomicSwap::fund_account_id()),
+            51
+        );
+
+        let lock_id = AcuityAtomicSwap::get_lock_id(B, A, hashed_secret, timeout);
+        assert_eq!(AcuityAtomicSwap::lock_id_value(lock_id), Some(value));
+    });
+}
+
+#[test]
+fn fill_signed_order_fail_other_instance() {
+    run_test(|| {
+        let order = SignedOrder {
+            maker: A,
+            sell_asset_id: AcuityAssetId::default(),
+            buy_asset_id: AcuityAssetId::default(),
+            price: 5,
+            expiry: Now::get() + 1000,
+            nonce: 0,
+        };
+        let signature = TestSignature(A, AcuityAtomicSwap::signed_order_message(&order));
+        let timeout = Now::get() + 1000;
+        assert_ok!(AcuityAtomicSwap::fill_signed_order(
+            RuntimeOrigin::signed(B),
+            order.clone(),
+            signature.clone(),
+            AcuityHashedSecret::default(),
+            timeout,
+            50
+        ));
+        assert_noop!(
+            AcuityAtomicSwapStable::fill_signed_order(
+                RuntimeOrigin::signed(B),
+                order.clone(),
+                signature,
+                AcuityHashedSecret::default(),
+                timeout,
+                20
+            ),
+            Error::<Test, Instance2>::InvalidOrderSignature
+        );
+        // A bare encoding of the order is not accepted either.
+        assert_noop!(
+            AcuityAtomicSwapStable::fill_signed_order(
+                RuntimeOrigin::signed(B),
+                order.clone(),
+                TestSignature(A, order.encode()),
+                AcuityHashedSecret::default(),
+                timeout,
+                20
+            ),
+            Error::<Test, Instance2>::InvalidOrderSignature
+        );
+    });
+}
+
+#[test]
+fn submit_quote_fail_request_not_exist() {
+    run_test(|| {
+        let now = Now::get();
+        assert_noop!(
+            AcuityAtomicSwap::submit_quote(RuntimeOrigin::signed(A), 0, 5, now + 1000),
+            Error::<Test>::QuoteRequestDoesNotExist
+        );
+    });
+}
+
+#[test]
+fn submit_quote_fail_too_many_quotes() {
+    run_test(|| {
+        let now = Now::get();
+        assert_ok!(AcuityAtomicSwap::request_quote(
+            RuntimeOrigin::signed(B),
+            AcuityAssetId::default(),
+            AcuityAssetId::default(),
+            50,
+            now + 1000
+        ));
+        assert_ok!(AcuityAtomicSwap::submit_quote(
+            RuntimeOrigin::signed(A),
+            0,
+            5,
+            now + 1000
+        ));
+        assert_ok!(AcuityAtomicSwap::submit_quote(
+            RuntimeOrigin::signed(3),
+            0,
+            6,
+            now + 1000
+        ));
+        // Replacing a quote does not take another slot.
+        assert_ok!(AcuityAtomicSwap::submit_quote(
+            RuntimeOrigin::signed(A),
+            0,
+            4,
+            now + 1000
+        ));
+        assert_noop!(
+            AcuityAtomicSwap::submit_quote(RuntimeOrigin::signed(4), 0, 7, now + 1000),
+            Error::<Test>::TooManyQuotes
+        );
+    });
+}
+
+#[test]
+fn submit_quote_fail_request_expired() {
+    run_test(|| {
+        let now = Now::get();
+        assert_ok!(AcuityAtomicSwap::request_quote(
+            RuntimeOrigin::signed(B),
+            AcuityAssetId::default(),
+            AcuityAssetId::default(),
+            50,
+            now + 1000
+        ));
+        Now::set(now + 1000);
+        assert_noop!(
+            AcuityAtomicSwap::submit_quote(RuntimeOrigin::signed(A), 0, 5, now + 2000),
+            Error::<Test>::QuoteRequestExpired
+        );
+    });
+}
+
+#[test]
+fn accept_quote_fail_price_mismatch() {
+    run_test(|| {
+        let now = Now::get();
+        assert_ok!(AcuityAtomicSwap::request_quote(
+            RuntimeOrigin::signed(B),
+            AcuityAssetId::default(),
+            AcuityAssetId::default(),
+            50,
+            now + 1000
+        ));
+        assert_ok!(AcuityAtomicSwap::submit_quote(
+            RuntimeOrigin::signed(A),
+            0,
+            5,
+            now + 1000
+        ));
+        assert_noop!(
+            AcuityAtomicSwap::accept_quote(
+                RuntimeOrigin::signed(B),
+                0,
+                A,
+                AcuityHashedSecret::default(),
+                now + 1000,
+                4
+            ),
+            Error::<Test>::QuotePriceMismatch
+        );
+        assert_noop!(
+            AcuityAtomicSwap::accept_quote(
+                RuntimeOrigin::signed(A),
+                0,
+                A,
+                AcuityHashedSecret::default(),
+                now + 1000,
+                5
+            ),
+            Error::<Test>::NotQuoteRequester
+        );
+    });
+}
+
+#[test]
+fn accept_quote() {
+    run_test(|| {
+        let hashed_secret = AcuityHashedSecret::default();
+        let now = Now::get();
+        let timeout = now + 1000;
+        assert_ok!(AcuityAtomicSwap::request_quote(
+            RuntimeOrigin::signed(B),
+            AcuityAssetId::default(),
+            AcuityAssetId::default(),
+            50,
+            now + 1000
+        ));
+        assert_ok!(AcuityAtomicSwap::submit_quote(
+            RuntimeOrigin::signed(A),
+            0,
+            5,
+            now + 1000
+        ));
+        assert_ok!(AcuityAtomicSwap::accept_quote(
+            RuntimeOrigin::signed(B),
+            0,
+            A,
+            hashed_secret,
+            timeout,
+            5
+        ));
+        assert_eq!(Balances::free_balance(B), 50);
+        let lock_id = AcuityAtomicSwap::get_lock_id(B, A, hashed_secret, timeout);
+        assert_eq!(AcuityAtomicSwap::lock_id_value(lock_id), Some(50));
+        assert_eq!(AcuityAtomicSwap::quote_request(0), None);
+        assert_eq!(AcuityAtomicSwap::quote(0, A), None);
+    });
+}
+
+#[test]
+fn cancel_quote_request() {
+    run_test(|| {
+        let now = Now::get();
+        assert_ok!(AcuityAtomicSwap::request_quote(
+            RuntimeOrigin::signed(B),
+            AcuityAssetId::default(),
+            AcuityAssetId::default(),
+            50,
+            now + 1000
+        ));
+        assert_noop!(
+            AcuityAtomicSwap::cancel_quote_request(RuntimeOrigin::signed(A), 0),
+            Error::<Test>::NotQuoteRequester
+        );
+        // Anyone can remove an expired request.
+        Now::set(now + 1000);
+        assert_ok!(AcuityAtomicSwap::cancel_quote_request(
+            RuntimeOrigin::signed(A),
+            0
+        ));
+        assert_eq!(AcuityAtomicSwap::quote_request(0), None);
+    });
+}
+
+#[test]
+fn request_quote_fail_too_many_requests() {
+    run_test(|| {
+        let now = Now::get();
+        for _ in 0..2 {
+            assert_ok!(AcuityAtomicSwap::request_quote(
+                RuntimeOrigin::signed(B),
+                AcuityAssetId::default(),
+                AcuityAssetId::default(),
+                50,
+                now + 1000
+            ));
+        }
+        assert_eq!(AcuityAtomicSwap::quote_request_count(B), 2);
+        assert_noop!(
+            AcuityAtomicSwap::request_quote(
+                RuntimeOrigin::signed(B),
+                AcuityAssetId::default(),
+                AcuityAssetId::default(),
+                50,
+                now + 1000
+            ),
+            Error::<Test>::TooManyQuoteRequests
+        );
+        // Removing a request frees its slot.
+        assert_ok!(AcuityAtomicSwap::cancel_quote_request(
+            RuntimeOrigin::signed(B),
+            0
+        ));
+        assert_eq!(AcuityAtomicSwap::quote_request_count(B), 1);
+        assert_ok!(AcuityAtomicSwap::request_quote(
+            RuntimeOrigin::signed(B),
+            AcuityAssetId::default(),
+            AcuityAssetId::default(),
+            50,
+            now + 1000
+        ));
+    });
+}
+
+#[test]
+fn quote_request_pruned() {
+    run_test(|| {
+        System::set_block_number(1);
+        let now = Now::get();
+        assert_ok!(AcuityAtomicSwap::request_quote(
+            RuntimeOrigin::signed(B),
+            AcuityAssetId::default(),
+            AcuityAssetId::default(),
+            50,
+            now + 1000
+        ));
+        assert_ok!(AcuityAtomicSwap::submit_quote(
+            RuntimeOrigin::signed(A),
+            0,
+            5,
+            now + 1000
+        ));
+        AcuityAtomicSwap::on_initialize(20);
+        assert!(AcuityAtomicSwap::quote_request(0).is_some());
+        AcuityAtomicSwap::on_initialize(21);
+        System::assert_last_event(RuntimeEvent::AcuityAtomicSwap(Event::QuoteRequestRemoved(
+            0,
+        )));
+        assert_eq!(AcuityAtomicSwap::quote_request(0), None);
+        assert_eq!(AcuityAtomicSwap::quote(0, A), None);
+        assert_eq!(AcuityAtomicSwap::quote_request_count(B), 0);
+    });
+}
+
+#[test]
+fn register_liquidity_provider_fail_invalid_swap_size() {
+    run_test(|| {
+        assert_noop!(
+            AcuityAtomicSwap::register_liquidity_provider(
+                RuntimeOrigin::signed(A),
+                AcuityAssetId::default(),
+                AcuityForeignAddress::default(),
+                20,
+                10
+            ),
+            Error::<Test>::InvalidSwapSize
+        );
+    });
+}
+
+#[test]
+fn register_liquidity_provider() {
+    run_test(|| {
+        assert_ok!(AcuityAtomicSwap::register_liquidity_provider(
+            RuntimeOrigin::signed(A),
+            AcuityAssetId::default(),
+            AcuityForeignAddress::default(),
+            10,
+            60
+        ));
+        assert_eq!(
+            AcuityAtomicSwap::liquidity_providers(AcuityAssetId::default()),
+            vec![(
+                A,
+                LiquidityProvider {
+                    foreign_address: AcuityForeignAddress::default(),
+                    min_value: 10,
+                    max_value: 60,
+                }
+            )]
+        );
+        assert_ok!(AcuityAtomicSwap::deregister_liquidity_provider(
+            RuntimeOrigin::signed(A),
+            AcuityAssetId::default()
+        ));
+        assert_eq!(
+            AcuityAtomicSwap::liquidity_providers(AcuityAssetId::default()),
+            vec![]
+        );
+        assert_noop!(
+            AcuityAtomicSwap::deregister_liquidity_provider(
+                RuntimeOrigin::signed(A),
+                AcuityAssetId::default()
+            ),
+            Error::<Test>::ProviderNotRegistered
+        );
+    });
+}
+
+#[test]
+fn lock_buy_fail_value_outside_swap_size() {
+    run_test(|| {
+        let now = Now::get();
+        assert_ok!(AcuityAtomicSwap::register_liquidity_provider(
+            RuntimeOrigin::signed(A),
+            AcuityAssetId::default(),
+            AcuityForeignAddress::default(),
+            10,
+            40
+        ));
+        assert_noop!(
+            AcuityAtomicSwap::lock_buy(
+                RuntimeOrigin::signed(B),
+                A,
+                AcuityHashedSecret::default(),
+                now + 1000,
+                50,
+                AcuityAssetId::default(),
+                5
+            ),
+            Error::<Test>::ValueOutsideSwapSize
+        );
+        assert_ok!(AcuityAtomicSwap::lock_buy(
+            RuntimeOrigin::signed(B),
+            A,
+            AcuityHashedSecret::default(),
+            now + 1000,
+            40,
+            AcuityAssetId::default(),
+            5
+        ));
+    });
+}
+
+#[test]
+fn lock_buy_fail_recipient_not_registered() {
+    run_test(|| {
+        RequireRegisteredRecipient::set(true);
+        let now = Now::get();
+        assert_noop!(
+            AcuityAtomicSwap::lock_buy(
+                RuntimeOrigin::signed(B),
+                A,
+                AcuityHashedSecret::default(),
+                now + 1000,
+                50,
+                AcuityAssetId::default(),
+                5
+            ),
+            Error::<Test>::RecipientNotRegistered
+        );
+        assert_ok!(AcuityAtomicSwap::register_liquidity_provider(
+            RuntimeOrigin::signed(A),
+            AcuityAssetId::default(),
+            AcuityForeignAddress::default(),
+            10,
+            60
+        ));
+        assert_ok!(AcuityAtomicSwap::lock_buy(
+            RuntimeOrigin::signed(B),
+            A,
+            AcuityHashedSecret::default(),
+            now + 1000,
+            50,
+            AcuityAssetId::default(),
+            5
+        ));
+        RequireRegisteredRecipient::set(false);
+    });
+}
+
+/// Address of the secp256k1 private key `[0x01; 32]`.
+const ETHEREUM_ADDRESS: [u8; 20] = [
+    0x1a, 0x64, 0x2f, 0x0e, 0x3c, 0x3a, 0xf5, 0x45, 0xe7, 0xac, 0xbd, 0x38, 0xb0, 0x72, 0x51, 0xb3,
+    0x99, 0x09, 0x14, 0xf1,
+];
+
+/// `personal_sign` by `ETHEREUM_ADDRESS` over the SCALE-encoded account id `A`.
+const ETHEREUM_SIGNATURE_A: [u8; 65] = [
+    0x07, 0x44, 0x28, 0xda, 0x9c, 0x8a, 0x6c, 0x6b, 0x6b, 0xe8, 0x40, 0x80, 0x2e, 0x34, 0x3b, 0xa2,
+    0x67, 0x60, 0x37, 0x84, 0xc4, 0xbe, 0x02, 0x6e, 0xa8, 0xa5, 0xf5, 0x86, 0xbe, 0x8d, 0xb6, 0x3d,
+    0x10, 0x80, 0x11, 0xe2, 0x80, 0x6e, 0x70, 0xde, 0x95, 0xda, 0x0c, 0x91, 0xe4, 0x3e, 0x15, 0xa6,
+    0x08, 0xea, 0x35, 0x77, 0x8f, 0x34, 0x8e, 0x35, 0xe8, 0xbb, 0xd0, 0x61, 0x9a, 0x60, 0x3e, 0xde,
+    0x1b,
+];
+
+/// `personal_sign` by `ETHEREUM_ADDRESS` over the SCALE-encoded account id `B`.
+const ETHEREUM_SIGNATURE_B: [u8; 65] = [
+    0x76, 0xbb, 0xd6, 0xd9, 0xe0, 0x19, 0xaa, 0xdf, 0x60, 0x18, 0xee, 0xb2, 0x2c, 0xfe, 0x5e, 0x89,
+    0x9d, 0xc0, 0x1d, 0x01, 0x06, 0x89, 0xff, 0x31, 0x3b, 0xbb, 0x0d, 0xda, 0x7e, 0x4c, 0x27, 0x46,
+    0x05, 0x09, 0x57, 0x9a, 0x08, 0x86, 0x8a, 0x76, 0x53, 0x2b, 0x3b, 0xc2, 0x85, 0x82, 0xe5, 0x86,
+    0xc1, 0x49, 0xa1, 0x17, 0xc3, 0x8b, 0xac, 0xa3, 0x06, 0x76, 0xdb, 0x98, 0xa1, 0x82, 0x81, 0x2d,
+    0x1c,
+];
+
+#[test]
+fn eth_recover() {
+    assert_eq!(
+        AcuityAtomicSwap::eth_recover(&EthereumSignature(ETHEREUM_SIGNATURE_A), &A.encode()),
+        Some(EthereumAddress(ETHEREUM_ADDRESS))
+    );
+    assert_ne!(
+        AcuityAtomicSwap::eth_recover(&EthereumSignature(ETHEREUM_SIGNATURE_A), &B.encode()),
+        Some(EthereumAddress(ETHEREUM_ADDRESS))
+    );
+}
+
+#[test]
+fn link_ethereum_address_fail_invalid_signature() {
+    run_test(|| {
+        assert_noop!(
+            AcuityAtomicSwap::link_ethereum_address(
+                RuntimeOrigin::signed(A),
+                EthereumAddress(ETHEREUM_ADDRESS),
+                EthereumSignature(ETHEREUM_SIGNATURE_B)
+            ),
+            Error::<Test>::InvalidEthereumSignature
+        );
+    });
+}
+
+#[test]
+fn link_ethereum_address_fail_already_linked() {
+    run_test(|| {
+        assert_ok!(AcuityAtomicSwap::link_ethereum_address(
+            RuntimeOrigin::signed(A),
+            EthereumAddress(ETHEREUM_ADDRESS),
+            EthereumSignature(ETHEREUM_SIGNATURE_A)
+        ));
+        assert_noop!(
+            AcuityAtomicSwap::link_ethereum_address(
+                RuntimeOrigin::signed(B),
+                EthereumAddress(ETHEREUM_ADDRESS),
+                EthereumSignature(ETHEREUM_SIGNATURE_B)
+            ),
+            Error::<Test>::EthereumAddressAlreadyLinked
+        );
+    });
+}
+
+#[test]
+fn link_ethereum_address() {
+    run_test(|| {
+        assert_ok!(AcuityAtomicSwap::link_ethereum_address(
+            RuntimeOrigin::signed(A),
+            EthereumAddress(ETHEREUM_ADDRESS),
+            EthereumSignature(ETHEREUM_SIGNATURE_A)
+        ));
+        assert_eq!(
+            AcuityAtomicSwap::account_ethereum_address(A),
+            Some(EthereumAddress(ETHEREUM_ADDRESS))
+        );
+        assert_eq!(
+            AcuityAtomicSwap::ethereum_address_account(EthereumAddress(ETHEREUM_ADDRESS)),
+            Some(A)
+        );
+
+        assert_ok!(AcuityAtomicSwap::unlink_ethereum_address(
+            RuntimeOrigin::signed(A)
+        ));
+        assert_eq!(AcuityAtomicSwap::account_ethereum_address(A), None);
+        assert_eq!(
+            AcuityAtomicSwap::ethereum_address_account(EthereumAddress(ETHEREUM_ADDRESS)),
+            None
+        );
+        assert_noop!(
+            AcuityAtomicSwap::unlink_ethereum_address(RuntimeOrigin::signed(A)),
+            Error::<Test>::EthereumAddressNotLinked
+        );
+
+        // The address can now be linked to another account.
+        assert_ok!(AcuityAtomicSwap::link_ethereum_address(
+            RuntimeOrigin::signed(B),
+            EthereumAddress(ETHEREUM_ADDRESS),
+            EthereumSignature(ETHEREUM_SIGNATURE_B)
+        ));
+    });
+}
+
+#[test]
+fn get_lock_id_abi_packed() {
+    // keccak256(abi.encodePacked(
+    //     0x1a642f0e3c3af545e7acbd38b07251b3990914f1,
+    //     0x2b5ad5c4795c026514f8317c7a215e218dccd6cf,
+    //     keccak256(bytes32(0)),
+    //     uint256(1000)
+    // ))
+    let sender = [
+        0x1a, 0x64, 0x2f, 0x0e, 0x3c, 0x3a, 0xf5, 0x45, 0xe7, 0xac, 0xbd, 0x38, 0xb0, 0x72, 0x51,
+        0xb3, 0x99, 0x09, 0x14, 0xf1,
+    ];
+    let recipient = [
+        0x2b, 0x5a, 0xd5, 0xc4, 0x79, 0x5c, 0x02, 0x65, 0x14, 0xf8, 0x31, 0x7c, 0x7a, 0x21, 0x5e,
+        0x21, 0x8d, 0xcc, 0xd6, 0xcf,
+    ];
+    let secret = AcuitySecret::default();
+    let mut hashed_secret = AcuityHashedSecret::default();
+    hashed_secret
+        .0
+        .copy_from_slice(&keccak_256(&secret.encode()));
+    assert_eq!(
+        AcuityAtomicSwap::get_lock_id_abi_packed(&sender, &recipient, hashed_secret, 1000),
+        AcuityLockId([
+            0x0e, 0x12, 0xb2, 0x34, 0x41, 0x0e, 0x25, 0xf4, 0xf7, 0x74, 0xf7, 0xb7, 0xfe, 0x99,
+            0x34, 0x86, 0x9e, 0x46, 0x52, 0x12, 0x5f, 0x43, 0xf0, 0x87, 0x52, 0x38, 0x9a, 0x1b,
+            0xe9, 0x5c, 0x82, 0x9a,
+        ])
+    );
+}
+
+#[test]
+fn unlock_keccak_abi_packed() {
+    run_test(|| {
+        MockLockIdScheme::set(LockIdScheme::KeccakAbiPacked);
+        let secret = AcuitySecret::default();
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&secret.encode()));
+        let timeout = Now::get() + 1000;
+        let lock_id = AcuityAtomicSwap::get_lock_id_abi_packed(
+            &B.encode(),
+            &A.encode(),
+            hashed_secret,
+            timeout.into(),
+        );
+        assert_eq!(
+            AcuityAtomicSwap::get_lock_id(B, A, hashed_secret, timeout),
+            lock_id
+        );
+
+        assert_ok!(AcuityAtomicSwap::lock_buy(
+            RuntimeOrigin::signed(B),
+            A,
+            hashed_secret,
+            timeout,
+            50,
+            AcuityAssetId::default(),
+            5
+        ));
+        assert_eq!(AcuityAtomicSwap::lock_id_value(lock_id), Some(50));
+        assert_ok!(AcuityAtomicSwap::unlock(
+            RuntimeOrigin::signed(A),
+            B,
+            secret,
+            timeout
+        ));
+        assert_eq!(AcuityAtomicSwap::lock_id_value(lock_id), None);
+        assert_eq!(Balances::free_balance(A), 150);
+        MockLockIdScheme::set(LockIdScheme::Blake2Scale);
+    });
+}
+
+#[test]
+fn lock_id_scheme_only_applies_to_hash_time_locks() {
+    run_test(|| {
+        let hashed_secret = AcuityHashedSecret::default();
+        let lock_ids = || {
+            [
+                AcuityAtomicSwap::get_nft_lock_id(&A, &B, hashed_secret, 1000),
+                AcuityAtomicSwap::get_ptlc_lock_id(&A, &B, AcuityPoint(PTLC_POINT), 1000),
+                AcuityAtomicSwap::get_threshold_lock_id(&A, &B, &[hashed_secret], 1, 1000),
+                AcuityAtomicSwap::get_split_lock_id(&A, hashed_secret, 1000, &[(B, 10)]),
+            ]
+        };
+        let blake2_scale = lock_ids();
+        MockLockIdScheme::set(LockIdScheme::KeccakAbiPacked);
+        assert_eq!(lock_ids(), blake2_scale);
+        MockLockIdScheme::set(LockIdScheme::Blake2Scale);
+    });
+}
+
+#[test]
+fn get_lock_id_domain_separated() {
+    run_test(|| {
+        let hashed_secret = AcuityHashedSecret::default();
+        let lock_id = AcuityAtomicSwap::get_lock_id(B, A, hashed_secret, 1000);
+        assert_ne!(
+            lock_id,
+            AcuityAtomicSwap::get_legacy_lock_id(B, A, hashed_secret, 1000)
+        );
+        ChainDomain::set([2; 32]);
+        assert_ne!(
+            lock_id,
+            AcuityAtomicSwap::get_lock_id(B, A, hashed_secret, 1000)
+        );
+        ChainDomain::set([1; 32]);
+    });
+}
+
+#[test]
+fn unlock_legacy_lock_id() {
+    run_test(|| {
+        let secret = AcuitySecret::default();
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&secret.encode()));
+        let timeout = Now::get() + 1000;
+        let value = 50;
+        // A lock created before lock ids were domain separated.
+        let lock_id = AcuityAtomicSwap::get_legacy_lock_id(B, A, hashed_secret, timeout);
+        LockIdValue::<Test>::insert(lock_id, value);
+        Balances::make_free_balance_be(&AcuityAtomicSwap::fund_account_id(), value + 1);
+
+        assert_ok!(AcuityAtomicSwap::unlock(
+            RuntimeOrigin::signed(A),
+            B,
+            secret,
+            timeout
+        ));
+        assert_eq!(Balances::free_balance(A), 150);
+        assert_eq!(AcuityAtomicSwap::lock_id_value(lock_id), None);
+    });
+}
+
+#[test]
+fn retrieve_legacy_lock_id() {
+    run_test(|| {
+        let hashed_secret = AcuityHashedSecret::default();
+        let timeout = Now::get();
+        let value = 50;
+        // A lock created before lock ids were domain separated.
+        let lock_id = AcuityAtomicSwap::get_legacy_lock_id(B, A, hashed_secret, timeout);
+        LockIdValue::<Test>::insert(lock_id, value);
+        Balances::make_free_balance_be(&AcuityAtomicSwap::fund_account_id(), value + 1);
+
+        assert_ok!(AcuityAtomicSwap::retrieve(
+            RuntimeOrigin::signed(B),
+            A,
+            hashed_secret,
+            timeout
+        ));
+        assert_eq!(Balances::free_balance(B), 150);
+        assert_eq!(AcuityAtomicSwap::lock_id_value(lock_id), None);
+    });
+}
+
+#[test]
+fn lock_buy_fail_shadows_legacy_lock_id() {
+    run_test(|| {
+        let secret = AcuitySecret::default();
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&secret.encode()));
+        let timeout = Now::get() + 1000;
+        let value = 50;
+        // A lock created before lock ids were domain separated.
+        let legacy_lock_id = AcuityAtomicSwap::get_legacy_lock_id(B, A, hashed_secret, timeout);
+        LockIdValue::<Test>::insert(legacy_lock_id, value);
+        Balances::make_free_balance_be(&AcuityAtomicSwap::fund_account_id(), value + 1);
+
+        assert_noop!(
+            AcuityAtomicSwap::lock_buy(
+                RuntimeOrigin::signed(B),
+                A,
+                hashed_secret,
+                timeout,
+                1,
+                AcuityAssetId::default(),
+                5
+            ),
+            Error::<Test>::LockAlreadyExists
+        );
+        // The retained state of a settled lock does not hide the open legacy lock.
+        let lock_id = AcuityAtomicSwap::get_lock_id(B, A, hashed_secret, timeout);
+        LockStates::<Test>::insert(lock_id, LockState::Declined);
+        assert_ok!(AcuityAtomicSwap::unlock(
+            RuntimeOrigin::signed(A),
+            B,
+            secret,
+            timeout
+        ));
+        assert_eq!(Balances::free_balance(A), 150);
+        assert_eq!(AcuityAtomicSwap::lock_id_value(legacy_lock_id), None);
+    });
+}
+
+#[test]
+fn instances_isolated() {
+    run_test(|| {
+        let secret = AcuitySecret::default();
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&secret.encode()));
+        let timeout = Now::get() + 1000;
+        let lock_id = AcuityAtomicSwap::get_lock_id(B, A, hashed_secret, timeout);
+        let stable_lock_id = AcuityAtomicSwapStable::get_lock_id(B, A, hashed_secret, timeout);
+        assert_ne!(
+            AcuityAtomicSwap::fund_account_id(),
+            AcuityAtomicSwapStable::fund_account_id()
+        );
+        assert_ne!(lock_id, stable_lock_id);
+
+        assert_ok!(AcuityAtomicSwap::lock_buy(
+            RuntimeOrigin::signed(B),
+            A,
+            hashed_secret,
+            timeout,
+            50,
+            AcuityAssetId::default(),
+            5
+        ));
+        assert_eq!(AcuityAtomicSwap::lock_id_value(lock_id), Some(50));
+        assert_eq!(AcuityAtomicSwapStable::lock_id_value(stable_lock_id), None);
+        assert_eq!(
+            Balances::free_balance(AcuityAtomicSwapStable::fund_account_id()),
+            1
+        );
+        assert_noop!(
+            AcuityAtomicSwapStable::unlock(RuntimeOrigin::signed(A), B, secret, timeout),
+            Error::<Test, Instance2>::LockDoesNotExist
+        );
+
+        assert_ok!(AcuityAtomicSwapStable::lock_buy(
+            RuntimeOrigin::signed(B),
+            A,
+            hashed_secret,
+            timeout,
+            20,
+            AcuityAssetId::default(),
+            5
+        ));
+        assert_eq!(
+            Balances::free_balance(AcuityAtomicSwap::fund_account_id()),
+            51
+        );
+        assert_eq!(
+            Balances::free_balance(AcuityAtomicSwapStable::fund_account_id()),
+            21
+        );
+
+        assert_ok!(AcuityAtomicSwapStable::unlock(
+            RuntimeOrigin::signed(A),
+            B,
+            secret,
+            timeout
+        ));
+        assert_eq!(Balances::free_balance(A), 120);
+        assert_eq!(AcuityAtomicSwap::lock_id_value(lock_id), Some(50));
+        assert_eq!(AcuityAtomicSwapStable::lock_id_value(stable_lock_id), None);
+    });
+}
+
+#[test]
+fn lock_windows() {
+    run_test(|| {
+        assert_eq!(AcuityAtomicSwap::unlock_deadline(1000), 1000);
+        assert_eq!(AcuityAtomicSwap::refund_from(1000), 1000);
+        UnlockCutoff::set(100);
+        RefundDelay::set(200);
+        assert_eq!(AcuityAtomicSwap::unlock_deadline(1000), 900);
+        assert_eq!(AcuityAtomicSwap::refund_from(1000), 1200);
+        assert_eq!(AcuityAtomicSwap::unlock_deadline(50), 0);
+        assert_eq!(AcuityAtomicSwap::refund_from(u64::MAX), u64::MAX);
+        UnlockCutoff::set(0);
+        RefundDelay::set(0);
+    });
+}
+
+#[test]
+fn unlock_unlock_cutoff() {
+    run_test(|| {
+        UnlockCutoff::set(100);
+        let secret = AcuitySecret::default();
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&secret.encode()));
+        let timeout = 1000;
+
+        assert_ok!(AcuityAtomicSwap::lock_buy(
+            RuntimeOrigin::signed(B),
+            A,
+            hashed_secret,
+            timeout,
+            50,
+            AcuityAssetId::default(),
+            5
+        ));
+        Now::set(900);
+        assert_noop!(
+            AcuityAtomicSwap::unlock(RuntimeOrigin::signed(A), B, secret, timeout),
+            Error::<Test>::LockTimedOut
+        );
+        Now::set(899);
+        assert_ok!(AcuityAtomicSwap::unlock(
+            RuntimeOrigin::signed(A),
+            B,
+            secret,
+            timeout
+        ));
+        UnlockCutoff::set(0);
+    });
+}
+
+#[test]
+fn retrieve_refund_delay() {
+    run_test(|| {
+        RefundDelay::set(200);
+        let hashed_secret = AcuityHashedSecret::default();
+        let timeout = 1000;
+
+        assert_ok!(AcuityAtomicSwap::lock_buy(
+            RuntimeOrigin::signed(B),
+            A,
+            hashed_secret,
+            timeout,
+            50,
+            AcuityAssetId::default(),
+            5
+        ));
+        Now::set(1199);
+        assert_noop!(
+            AcuityAtomicSwap::retrieve(RuntimeOrigin::signed(B), A, hashed_secret, timeout),
+            Error::<Test>::LockNotTimedOut
+        );
+        Now::set(1200);
+        assert_ok!(AcuityAtomicSwap::retrieve(
+            RuntimeOrigin::signed(B),
+            A,
+            hashed_secret,
+            timeout
+        ));
+        RefundDelay::set(0);
+    });
+}
+
+#[test]
+fn unlock_fail_already_unlocked() {
+    run_test(|| {
+        let secret = AcuitySecret::default();
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&secret.encode()));
+        let timeout = Now::get() + 1000;
+        let lock_id = AcuityAtomicSwap::get_lock_id(B, A, hashed_secret, timeout);
+
+        assert_ok!(AcuityAtomicSwap::lock_buy(
+            RuntimeOrigin::signed(B),
+            A,
+            hashed_secret,
+            timeout,
+            50,
+            AcuityAssetId::default(),
+            5
+        ));
+        assert_eq!(AcuityAtomicSwap::lock_state(lock_id), Some(LockState::Open));
+        assert_ok!(AcuityAtomicSwap::unlock(
+            RuntimeOrigin::signed(A),
+            B,
+            secret,
+            timeout
+        ));
+        assert_eq!(
+            AcuityAtomicSwap::lock_state(lock_id),
+            Some(LockState::Unlocked { secret })
+        );
+        assert_noop!(
+            AcuityAtomicSwap::unlock(RuntimeOrigin::signed(A), B, secret, timeout),
+            Error::<Test>::LockAlreadyUnlocked
+        );
+        assert_noop!(
+            AcuityAtomicSwap::decline(RuntimeOrigin::signed(A), B, hashed_secret, timeout),
+            Error::<Test>::LockAlreadyUnlocked
+        );
+        assert_noop!(
+            AcuityAtomicSwap::lock_buy(
+                RuntimeOrigin::signed(B),
+                A,
+                hashed_secret,
+                timeout,
+                50,
+                AcuityAssetId::default(),
+                5
+            ),
+            Error::<Test>::LockAlreadyExists
+        );
+    });
+}
+
+#[test]
+fn unlock_fail_already_declined() {
+    run_test(|| {
+        let secret = AcuitySecret::default();
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&secret.encode()));
+        let timeout = Now::get() + 1000;
+
+        assert_ok!(AcuityAtomicSwap::lock_buy(
+            RuntimeOrigin::signed(B),
+            A,
+            hashed_secret,
+            timeout,
+            50,
+            AcuityAssetId::default(),
+            5
+        ));
+        assert_ok!(AcuityAtomicSwap::decline(
+            RuntimeOrigin::signed(A),
+            B,
+            hashed_secret,
+            timeout
+        ));
+        assert_noop!(
+            AcuityAtomicSwap::unlock(RuntimeOrigin::signed(A), B, secret, timeout),
+            Error::<Test>::LockAlreadyDeclined
+        );
+    });
+}
+
+#[test]
+fn retrieve_fail_already_retrieved() {
+    run_test(|| {
+        let hashed_secret = AcuityHashedSecret::default();
+        let timeout = Now::get();
+
+        assert_ok!(AcuityAtomicSwap::lock_buy(
+            RuntimeOrigin::signed(B),
+            A,
+            hashed_secret,
+            timeout,
+            50,
+            AcuityAssetId::default(),
+            5
+        ));
+        assert_ok!(AcuityAtomicSwap::retrieve(
+            RuntimeOrigin::signed(B),
+            A,
+            hashed_secret,
+            timeout
+        ));
+        assert_noop!(
+            AcuityAtomicSwap::retrieve(RuntimeOrigin::signed(B), A, hashed_secret, timeout),
+            Error::<Test>::LockAlreadyRetrieved
+        );
+    });
+}
+
+#[test]
+fn lock_state_pruned() {
+    run_test(|| {
+        System::set_block_number(1);
+        let hashed_secret = AcuityHashedSecret::default();
+        let timeout = Now::get();
+        let lock_id = AcuityAtomicSwap::get_lock_id(B, A, hashed_secret, timeout);
+
+        assert_ok!(AcuityAtomicSwap::lock_buy(
+            RuntimeOrigin::signed(B),
+            A,
+            hashed_secret,
+            timeout,
+            50,
+            AcuityAssetId::default(),
+            5
+        ));
+        assert_ok!(AcuityAtomicSwap::retrieve(
+            RuntimeOrigin::signed(B),
+            A,
+            hashed_secret,
+            timeout
+        ));
+        AcuityAtomicSwap::on_initialize(10);
+        assert_eq!(
+            AcuityAtomicSwap::lock_state(lock_id),
+            Some(LockState::Retrieved)
+        );
+        AcuityAtomicSwap::on_initialize(11);
+        assert_eq!(AcuityAtomicSwap::lock_state(lock_id), None);
+        assert_noop!(
+            AcuityAtomicSwap::retrieve(RuntimeOrigin::signed(B), A, hashed_secret, timeout),
+            Error::<Test>::LockDoesNotExist
+        );
+    });
+}
+
+#[test]
+fn lock_state_pruned_carried_over() {
+    run_test(|| {
+        for i in 0..300u32 {
+            let mut lock_id = AcuityLockId::default();
+            lock_id.0[..4].copy_from_slice(&i.to_be_bytes());
+            LockStates::<Test>::insert(lock_id, LockState::Retrieved);
+            LockStateExpiry::<Test>::insert(5, lock_id, ());
+        }
+        // Pruning is capped per block and the rest carries over to the next block.
+        AcuityAtomicSwap::on_initialize(5);
+        assert_eq!(LockStates::<Test>::iter().count(), 45);
+        assert_eq!(LockStatePruneCursor::<Test>::get(), Some(5));
+        AcuityAtomicSwap::on_initialize(6);
+        assert_eq!(LockStates::<Test>::iter().count(), 0);
+        assert_eq!(LockStateExpiry::<Test>::iter().count(), 0);
+        assert_eq!(LockStatePruneCursor::<Test>::get(), Some(7));
+    });
+}
+
+#[test]
+fn lock_state_pruned_zero_retention() {
+    run_test(|| {
+        LockStateRetention::set(0);
+        System::set_block_number(1);
+        let hashed_secret = AcuityHashedSecret::default();
+        let timeout = Now::get();
+        let lock_id = AcuityAtomicSwap::get_lock_id(B, A, hashed_secret, timeout);
+
+        assert_ok!(AcuityAtomicSwap::lock_buy(
+            RuntimeOrigin::signed(B),
+            A,
+            hashed_secret,
+            timeout,
+            50,
+            AcuityAssetId::default(),
+            5
+        ));
+        assert_ok!(AcuityAtomicSwap::retrieve(
+            RuntimeOrigin::signed(B),
+            A,
+            hashed_secret,
+            timeout
+        ));
+        // The state is pruned in the next block rather than never.
+        AcuityAtomicSwap::on_initialize(2);
+        assert_eq!(AcuityAtomicSwap::lock_state(lock_id), None);
+        LockStateRetention::set(10);
+    });
+}
+
+#[test]
+fn lock_buy_fail_insufficient_balance() {
+    run_test(|| {
+        let now = Now::get();
+        assert_noop!(
+            AcuityAtomicSwap::lock_buy(
+                RuntimeOrigin::signed(B),
+                A,
+                AcuityHashedSecret::default(),
+                now + 1000,
+                101,
+                AcuityAssetId::default(),
+                5
+            ),
+            Error::<Test>::InsufficientBalance
+        );
+    });
+}
+
+#[test]
+fn lock_buy_fail_would_kill_creator() {
+    run_test(|| {
+        let now = Now::get();
+        assert_noop!(
+            AcuityAtomicSwap::lock_buy(
+                RuntimeOrigin::signed(B),
+                A,
+                AcuityHashedSecret::default(),
+                now + 1000,
+                100,
+                AcuityAssetId::default(),
+                5
+            ),
+            Error::<Test>::WouldKillCreator
+        );
+        assert_ok!(AcuityAtomicSwap::lock_buy(
+            RuntimeOrigin::signed(B),
+            A,
+            AcuityHashedSecret::default(),
+            now + 1000,
+            99,
+            AcuityAssetId::default(),
+            5
+        ));
+        assert_eq!(Balances::free_balance(B), 1);
+    });
+}
+
+#[test]
+fn lock_sell_fail_would_kill_creator() {
+    run_test(|| {
+        let now = Now::get();
+        assert_noop!(
+            AcuityAtomicSwap::lock_sell(
+                RuntimeOrigin::signed(B),
+                A,
+                AcuityHashedSecret::default(),
+                now + 1000,
+                100,
+                AcuityAssetId::default(),
+                AcuityLockId::default()
+            ),
+            Error::<Test>::WouldKillCreator
+        );
+        assert_ok!(AcuityAtomicSwap::lock_sell(
+            RuntimeOrigin::signed(B),
+            A,
+            AcuityHashedSecret::default(),
+            now + 1000,
+            99,
+            AcuityAssetId::default(),
+            AcuityLockId::default()
+        ));
+    });
+}
+
+#[test]
+fn unlock_fail_pot_underfunded() {
+    run_test(|| {
+        let secret = AcuitySecret::default();
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&secret.encode()));
+        let timeout = Now::get() + 1000;
+
+        assert_ok!(AcuityAtomicSwap::lock_buy(
+            RuntimeOrigin::signed(B),
+            A,
+            hashed_secret,
+            timeout,
+            50,
+            AcuityAssetId::default(),
+            5
+        ));
+        Balances::make_free_balance_be(&AcuityAtomicSwap::fund_account_id(), 50);
+        assert_noop!(
+            AcuityAtomicSwap::unlock(RuntimeOrigin::signed(A), B, secret, timeout),
+            Error::<Test>::PotUnderfunded
+        );
+        Balances::make_free_balance_be(&AcuityAtomicSwap::fund_account_id(), 51);
+    });
+}
+
+#[test]
+fn unlock_fail_recipient_below_existential_deposit() {
+    run_test(|| {
+        const C: u64 = 3;
+        ExistentialDeposit::set(10);
+        let secret = AcuitySecret::default();
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&secret.encode()));
+        let timeout = Now::get() + 1000;
+        Balances::make_free_balance_be(&AcuityAtomicSwap::fund_account_id(), 10);
+
+        assert_ok!(AcuityAtomicSwap::lock_buy(
+            RuntimeOrigin::signed(B),
+            C,
+            hashed_secret,
+            timeout,
+            5,
+            AcuityAssetId::default(),
+            5
+        ));
+        assert_noop!(
+            AcuityAtomicSwap::unlock(RuntimeOrigin::signed(C), B, secret, timeout),
+            Error::<Test>::RecipientBelowExistentialDeposit
+        );
+        ExistentialDeposit::set(1);
+    });
+}
+
+#[test]
+fn provision_fund_account() {
+    run_test(|| {
+        let fund_account_id = AcuityAtomicSwap::fund_account_id();
+        assert_eq!(Balances::free_balance(fund_account_id), 1);
+        assert_ok!(AcuityAtomicSwap::do_try_state());
+
+        System::set_block_number(1);
+        Balances::make_free_balance_be(&fund_account_id, 0);
+        assert!(AcuityAtomicSwap::do_try_state().is_err());
+        let issuance = Balances::total_issuance();
+        migrations::ProvisionFundAccount::<Test>::on_runtime_upgrade();
+        assert_eq!(Balances::free_balance(fund_account_id), 1);
+        assert_eq!(Balances::total_issuance(), issuance + 1);
+        System::assert_last_event(RuntimeEvent::AcuityAtomicSwap(
+            Event::FundAccountProvisioned(1),
+        ));
+        migrations::ProvisionFundAccount::<Test>::on_runtime_upgrade();
+        assert_eq!(Balances::free_balance(fund_account_id), 1);
+        assert_ok!(AcuityAtomicSwap::do_try_state());
+    });
+}
+
+#[test]
+fn try_state_fail_zero_value_lock() {
+    new_test_ext().execute_with(|| {
+        LockIdValue::<Test>::insert(AcuityLockId::default(), 0);
+        assert!(AcuityAtomicSwap::do_try_state().is_err());
+    });
+}
+
+#[test]
+fn try_state_fail_settled_lock_with_value() {
+    new_test_ext().execute_with(|| {
+        Balances::make_free_balance_be(&AcuityAtomicSwap::fund_account_id(), 51);
+        LockIdValue::<Test>::insert(AcuityLockId::default(), 50);
+        assert_ok!(AcuityAtomicSwap::do_try_state());
+        LockStates::<Test>::insert(AcuityLockId::default(), LockState::Declined);
+        assert!(AcuityAtomicSwap::do_try_state().is_err());
+    });
+}
+
+#[cfg(feature = "try-runtime")]
+#[test]
+fn provision_fund_account_try_on_runtime_upgrade() {
+    run_test(|| {
+        Balances::make_free_balance_be(&AcuityAtomicSwap::fund_account_id(), 0);
+        assert_ok!(migrations::ProvisionFundAccount::<Test>::try_on_runtime_upgrade(true));
+        assert_eq!(
+            Balances::free_balance(AcuityAtomicSwap::fund_account_id()),
+            1
+        );
+    });
+}
+
+#[test]
+fn migrate_v0_to_v1() {
+    new_test_ext().execute_with(|| {
+        StorageVersion::new(0).put::<AcuityAtomicSwap>();
+        Balances::make_free_balance_be(&AcuityAtomicSwap::fund_account_id(), 0);
+        migrations::v1::MigrateV0ToV1::<Test>::on_runtime_upgrade();
+        assert_eq!(
+            Balances::free_balance(AcuityAtomicSwap::fund_account_id()),
+            1
+        );
+        assert_eq!(
+            AcuityAtomicSwap::on_chain_storage_version(),
+            StorageVersion::new(1)
+        );
+        // Running again is a no-op.
+        Balances::make_free_balance_be(&AcuityAtomicSwap::fund_account_id(), 0);
+        migrations::v1::MigrateV0ToV1::<Test>::on_runtime_upgrade();
+        assert_eq!(
+            Balances::free_balance(AcuityAtomicSwap::fund_account_id()),
+            0
+        );
+    });
+}
+
+#[test]
+fn migrate_v1_to_v2() {
+    run_test(|| {
+        // Snapshot of a version 1 chain with open locks that have no state.
+        StorageVersion::new(1).put::<AcuityAtomicSwap>();
+        let lock_ids: Vec<AcuityLockId> = (1..=5u8).map(|i| AcuityLockId([i; 32])).collect();
+        for lock_id in &lock_ids {
+            LockIdValue::<Test>::insert(lock_id, 10);
+        }
+        LockStates::<Test>::insert(lock_ids[4], LockState::Open);
+        Balances::make_free_balance_be(&AcuityAtomicSwap::fund_account_id(), 51);
+
+        migrations::v2::MigrateV1ToV2::<Test>::on_runtime_upgrade();
+        assert_eq!(
+            AcuityAtomicSwap::on_chain_storage_version(),
+            StorageVersion::new(2)
+        );
+        assert_eq!(
+            LockStateBackfillCursor::<Test>::get(),
+            Some(MigrationCursor::Start)
+        );
+
+        assert_eq!(migrations::v2::step::<Test, ()>(2), 2);
+        assert_eq!(LockStates::<Test>::iter().count(), 3);
+        assert_eq!(migrations::v2::step::<Test, ()>(2), 2);
+        assert_eq!(migrations::v2::step::<Test, ()>(2), 1);
+        assert_eq!(LockStateBackfillCursor::<Test>::get(), None);
+        assert_eq!(migrations::v2::step::<Test, ()>(2), 0);
+        for lock_id in &lock_ids {
+            assert_eq!(LockStates::<Test>::get(lock_id), Some(LockState::Open));
+        }
+    });
+}
+
+#[test]
+fn migrate_v1_to_v2_on_idle() {
+    run_test(|| {
+        StorageVersion::new(1).put::<AcuityAtomicSwap>();
+        for i in 1..=5u8 {
+            LockIdValue::<Test>::insert(AcuityLockId([i; 32]), 10);
+        }
+        Balances::make_free_balance_be(&AcuityAtomicSwap::fund_account_id(), 51);
+        migrations::v2::MigrateV1ToV2::<Test>::on_runtime_upgrade();
+
+        AcuityAtomicSwap::on_idle(1, Weight::MAX);
+        assert_eq!(LockStates::<Test>::iter().count(), 5);
+        assert_eq!(LockStateBackfillCursor::<Test>::get(), None);
+    });
+}
+
+#[test]
+fn genesis_seeds_state() {
+    let secret = AcuitySecret::default();
+    let mut hashed_secret = AcuityHashedSecret::default();
+    hashed_secret
+        .0
+        .copy_from_slice(&keccak_256(&secret.encode()));
+    let auction = DutchAuction {
+        start_price: 10,
+        end_price: 5,
+        start: 0,
+        end: 100,
+    };
+    let provider = LiquidityProvider {
+        foreign_address: AcuityForeignAddress::default(),
+        min_value: 10,
+        max_value: 20,
+    };
+    let genesis = GenesisConfig::<Test> {
+        locks: vec![
+            (A, B, hashed_secret, 1000, 50),
+            (B, A, hashed_secret, 2000, 30),
+        ],
+        sell_auctions: vec![(A, AcuityAssetId::default(), auction.clone())],
+        liquidity_providers: vec![(AcuityAssetId::default(), B, provider.clone())],
+        ..Default::default()
+    };
+    new_test_ext_with_genesis(genesis).execute_with(|| {
+        let lock_id = AcuityAtomicSwap::get_lock_id(A, B, hashed_secret, 1000);
+        assert_eq!(AcuityAtomicSwap::lock_id_value(lock_id), Some(50));
+        assert_eq!(AcuityAtomicSwap::lock_state(lock_id), Some(LockState::Open));
+        let lock_id = AcuityAtomicSwap::get_lock_id(B, A, hashed_secret, 2000);
+        assert_eq!(AcuityAtomicSwap::lock_id_value(lock_id), Some(30));
+        assert_eq!(
+            Balances::free_balance(AcuityAtomicSwap::fund_account_id()),
+            81
+        );
+        assert_eq!(
+            AcuityAtomicSwap::sell_auction(A, AcuityAssetId::default()),
+            Some(auction)
+        );
+        assert_eq!(
+            AcuityAtomicSwap::liquidity_provider(AcuityAssetId::default(), B),
+            Some(provider)
+        );
+        assert_ok!(AcuityAtomicSwap::do_try_state());
+
+        // Seeded locks can be settled.
+        assert_ok!(AcuityAtomicSwap::unlock(
+            RuntimeOrigin::signed(B),
+            A,
+            secret,
+            1000
+        ));
+        assert_eq!(Balances::free_balance(B), 150);
+        assert_ok!(AcuityAtomicSwap::do_try_state());
+    });
+}
+
+#[test]
+#[should_panic(expected = "Duplicate genesis lock.")]
+fn genesis_fail_duplicate_lock() {
+    let mut hashed_secret = AcuityHashedSecret::default();
+    hashed_secret
+        .0
+        .copy_from_slice(&keccak_256(&AcuitySecret::default().encode()));
+    let genesis = GenesisConfig::<Test> {
+        locks: vec![
+            (A, B, hashed_secret, 1000, 50),
+            (A, B, hashed_secret, 1000, 20),
+        ],
+        ..Default::default()
+    };
+    new_test_ext_with_genesis(genesis);
+}
+
+#[test]
+#[should_panic(expected = "Genesis lock has zero value.")]
+fn genesis_fail_zero_value_lock() {
+    let mut hashed_secret = AcuityHashedSecret::default();
+    hashed_secret
+        .0
+        .copy_from_slice(&keccak_256(&AcuitySecret::default().encode()));
+    let genesis = GenesisConfig::<Test> {
+        locks: vec![(A, B, hashed_secret, 1000, 0)],
+        ..Default::default()
+    };
+    new_test_ext_with_genesis(genesis);
+}
+
+#[test]
+#[should_panic(expected = "Invalid genesis sell auction.")]
+fn genesis_fail_invalid_auction() {
+    let genesis = GenesisConfig::<Test> {
+        sell_auctions: vec![(
+            A,
+            AcuityAssetId::default(),
+            DutchAuction {
+                start_price: 5,
+                end_price: 10,
+                start: 0,
+                end: 100,
+            },
+        )],
+        ..Default::default()
+    };
+    new_test_ext_with_genesis(genesis);
+}
+
+#[test]
+fn lock_sell_batch() {
+    run_test(|| {
+        System::set_block_number(1);
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&AcuitySecret::default().encode()));
+        let locks: Vec<LockSellSpec<u64, u64, u128>> = (1..=3)
+            .map(|i| LockSellSpec {
+                recipient: B,
+                hashed_secret,
+                timeout: 1000 + i,
+                value: 10 * i as u128,
+                buy_asset_id: AcuityAssetId::default(),
+                buy_lock_id: AcuityLockId::default(),
+            })
+            .collect();
+        assert_ok!(AcuityAtomicSwap::lock_sell_batch(
+            RuntimeOrigin::signed(A),
+            locks.try_into().unwrap()
+        ));
+        for i in 1..=3 {
+            let lock_id = AcuityAtomicSwap::get_lock_id(A, B, hashed_secret, 1000 + i);
+            assert_eq!(
+                AcuityAtomicSwap::lock_id_value(lock_id),
+                Some(10 * i as u128)
+            );
+        }
+        assert_eq!(Balances::free_balance(A), 40);
+        assert_eq!(
+            Balances::free_balance(AcuityAtomicSwap::fund_account_id()),
+            61
+        );
+        let lock_events = System::events()
+            .into_iter()
+            .filter(|record| {
+                matches!(
+                    record.event,
+                    RuntimeEvent::AcuityAtomicSwap(Event::LockSell(..))
+                )
+            })
+            .count();
+        assert_eq!(lock_events, 3);
+    });
+}
+
+#[test]
+fn lock_sell_batch_fail_empty() {
+    run_test(|| {
+        assert_noop!(
+            AcuityAtomicSwap::lock_sell_batch(RuntimeOrigin::signed(A), Default::default()),
+            Error::<Test>::EmptyBatch
+        );
+    });
+}
+
+#[test]
+fn lock_sell_batch_fail_duplicate_lock() {
+    run_test(|| {
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&AcuitySecret::default().encode()));
+        let lock = LockSellSpec {
+            recipient: B,
+            hashed_secret,
+            timeout: 1000,
+            value: 10,
+            buy_asset_id: AcuityAssetId::default(),
+            buy_lock_id: AcuityLockId::default(),
+        };
+        assert_noop!(
+            AcuityAtomicSwap::lock_sell_batch(
+                RuntimeOrigin::signed(A),
+                vec![lock.clone(), lock].try_into().unwrap()
+            ),
+            Error::<Test>::LockAlreadyExists
+        );
+    });
+}
+
+#[test]
+fn lock_buy_batch() {
+    run_test(|| {
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&AcuitySecret::default().encode()));
+        let locks: Vec<LockBuySpec<u64, u64, u128>> = (1..=2)
+            .map(|i| LockBuySpec {
+                recipient: A,
+                hashed_secret,
+                timeout: 1000 + i,
+                value: 20,
+                sell_asset_id: AcuityAssetId::default(),
+                sell_price: 5,
+            })
+            .collect();
+        assert_ok!(AcuityAtomicSwap::lock_buy_batch(
+            RuntimeOrigin::signed(B),
+            locks.try_into().unwrap()
+        ));
+        assert_eq!(Balances::free_balance(B), 60);
+        assert_eq!(
+            Balances::free_balance(AcuityAtomicSwap::fund_account_id()),
+            41
+        );
+    });
+}
+
+#[test]
+fn lock_buy_batch_fail_all_or_nothing() {
+    run_test(|| {
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&AcuitySecret::default().encode()));
+        let lock = |timeout, value| LockBuySpec {
+            recipient: A,
+            hashed_secret,
+            timeout,
+            value,
+            sell_asset_id: AcuityAssetId::default(),
+            sell_price: 5,
+        };
+        // The last lock is invalid, so none are created.
+        assert_noop!(
+            AcuityAtomicSwap::lock_buy_batch(
+                RuntimeOrigin::signed(B),
+                vec![lock(1000, 20), lock(1001, 0)].try_into().unwrap()
+            ),
+            Error::<Test>::ZeroValue
+        );
+        // The total value is more than the creator can lock.
+        assert_noop!(
+            AcuityAtomicSwap::lock_buy_batch(
+                RuntimeOrigin::signed(B),
+                vec![lock(1000, 60), lock(1001, 60)].try_into().unwrap()
+            ),
+            Error::<Test>::InsufficientBalance
+        );
+    });
+}
+
+#[test]
+fn retrieve_all_expired() {
+    run_test(|| {
+        System::set_block_number(1);
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&AcuitySecret::default().encode()));
+        assert_ok!(AcuityAtomicSwap::lock_sell(
+            RuntimeOrigin::signed(A),
+            B,
+            hashed_secret,
+            1000,
+            10,
+            AcuityAssetId::default(),
+            AcuityLockId::default()
+        ));
+        let expired_a = AcuityAtomicSwap::get_lock_id(A, B, hashed_secret, 1000);
+        assert_ok!(AcuityAtomicSwap::lock_sell(
+            RuntimeOrigin::signed(A),
+            B,
+            hashed_secret,
+            1500,
+            20,
+            AcuityAssetId::default(),
+            AcuityLockId::default()
+        ));
+        let expired_b = AcuityAtomicSwap::get_lock_id(A, B, hashed_secret, 1500);
+        assert_ok!(AcuityAtomicSwap::lock_sell(
+            RuntimeOrigin::signed(A),
+            B,
+            hashed_secret,
+            3000,
+            30,
+            AcuityAssetId::default(),
+            AcuityLockId::default()
+        ));
+        let open = AcuityAtomicSwap::get_lock_id(A, B, hashed_secret, 3000);
+        assert_eq!(Balances::free_balance(A), 40);
+        Now::set(2000);
+        assert_ok!(AcuityAtomicSwap::retrieve_all_expired(
+            RuntimeOrigin::signed(A),
+            10
+        ));
+        assert_eq!(Balances::free_balance(A), 70);
+        assert_eq!(AcuityAtomicSwap::lock_id_value(expired_a), None);
+        assert_eq!(AcuityAtomicSwap::lock_id_value(expired_b), None);
+        assert_eq!(
+            AcuityAtomicSwap::lock_state(expired_a),
+            Some(LockState::Retrieved)
+        );
+        assert_eq!(AcuityAtomicSwap::lock_id_value(open), Some(30));
+        assert_eq!(AcuityAtomicSwap::lock_info(expired_a), None);
+        let retrieve_events = System::events()
+            .into_iter()
+            .filter(|record| {
+                matches!(
+                    record.event,
+                    RuntimeEvent::AcuityAtomicSwap(Event::Retrieve(..))
+                )
+            })
+            .count();
+        assert_eq!(retrieve_events, 2);
+        assert_noop!(
+            AcuityAtomicSwap::retrieve_all_expired(RuntimeOrigin::signed(A), 10),
+            Error::<Test>::NoExpiredLocks
+        );
+    });
+}
+
+#[test]
+fn retrieve_all_expired_control_max() {
+    run_test(|| {
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&AcuitySecret::default().encode()));
+        assert_ok!(AcuityAtomicSwap::lock_sell(
+            RuntimeOrigin::signed(A),
+            B,
+            hashed_secret,
+            1000,
+            10,
+            AcuityAssetId::default(),
+            AcuityLockId::default()
+        ));
+        assert_ok!(AcuityAtomicSwap::lock_sell(
+            RuntimeOrigin::signed(A),
+            B,
+            hashed_secret,
+            1001,
+            10,
+            AcuityAssetId::default(),
+            AcuityLockId::default()
+        ));
+        assert_ok!(AcuityAtomicSwap::lock_sell(
+            RuntimeOrigin::signed(A),
+            B,
+            hashed_secret,
+            1002,
+            10,
+            AcuityAssetId::default(),
+            AcuityLockId::default()
+        ));
+        Now::set(2000);
+        assert_ok!(AcuityAtomicSwap::retrieve_all_expired(
+            RuntimeOrigin::signed(A),
+            2
+        ));
+        assert_eq!(Balances::free_balance(A), 90);
+        assert_ok!(AcuityAtomicSwap::retrieve_all_expired(
+            RuntimeOrigin::signed(A),
+            2
+        ));
+        assert_eq!(Balances::free_balance(A), 100);
+    });
+}
+
+#[test]
+fn retrieve_all_expired_control_open_locks() {
+    run_test(|| {
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&AcuitySecret::default().encode()));
+        for timeout in [5000, 5001, 1000] {
+            assert_ok!(AcuityAtomicSwap::lock_sell(
+                RuntimeOrigin::signed(A),
+                B,
+                hashed_secret,
+                timeout,
+                10,
+                AcuityAssetId::default(),
+                AcuityLockId::default()
+            ));
+        }
+        Now::set(2000);
+        // Open locks do not take up the max.
+        assert_ok!(AcuityAtomicSwap::retrieve_all_expired(
+            RuntimeOrigin::signed(A),
+            1
+        ));
+        assert_eq!(Balances::free_balance(A), 80);
+        assert_eq!(
+            AcuityAtomicSwap::lock_state(AcuityAtomicSwap::get_lock_id(A, B, hashed_secret, 1000)),
+            Some(LockState::Retrieved)
+        );
+    });
+}
+
+#[test]
+fn retrieve_all_expired_fail_no_expired_locks() {
+    run_test(|| {
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&AcuitySecret::default().encode()));
+        assert_ok!(AcuityAtomicSwap::lock_sell(
+            RuntimeOrigin::signed(A),
+            B,
+            hashed_secret,
+            1000,
+            10,
+            AcuityAssetId::default(),
+            AcuityLockId::default()
+        ));
+        assert_noop!(
+            AcuityAtomicSwap::retrieve_all_expired(RuntimeOrigin::signed(A), 10),
+            Error::<Test>::NoExpiredLocks
+        );
+        // Locks of other creators are not retrieved.
+        Now::set(2000);
+        assert_noop!(
+            AcuityAtomicSwap::retrieve_all_expired(RuntimeOrigin::signed(B), 10),
+            Error::<Test>::NoExpiredLocks
+        );
+    });
+}
+
+#[test]
+fn retrieve_all_expired_many_locks() {
+    run_test(|| {
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&AcuitySecret::default().encode()));
+        // A creator is not limited in how many locks they hold.
+        for timeout in 1000..1012 {
+            assert_ok!(AcuityAtomicSwap::lock_sell(
+                RuntimeOrigin::signed(A),
+                B,
+                hashed_secret,
+                timeout,
+                1,
+                AcuityAssetId::default(),
+                AcuityLockId::default()
+            ));
+        }
+        assert_eq!(Balances::free_balance(A), 88);
+        Now::set(2000);
+        // Each call examines at most max locks.
+        assert_ok!(AcuityAtomicSwap::retrieve_all_expired(
+            RuntimeOrigin::signed(A),
+            8
+        ));
+        assert_eq!(Balances::free_balance(A), 96);
+        assert_ok!(AcuityAtomicSwap::retrieve_all_expired(
+            RuntimeOrigin::signed(A),
+            8
+        ));
+        assert_eq!(Balances::free_balance(A), 100);
+    });
+}
+
+#[test]
+fn unlock_by_secret() {
+    run_test(|| {
+        System::set_block_number(1);
+        let secret = AcuitySecret::default();
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&secret.encode()));
+        assert_ok!(AcuityAtomicSwap::lock_sell(
+            RuntimeOrigin::signed(A),
+            B,
+            hashed_secret,
+            1000,
+            10,
+            AcuityAssetId::default(),
+            AcuityLockId::default()
+        ));
+        let lock_a = AcuityAtomicSwap::get_lock_id(A, B, hashed_secret, 1000);
+        assert_ok!(AcuityAtomicSwap::lock_sell(
+            RuntimeOrigin::signed(A),
+            B,
+            hashed_secret,
+            3000,
+            20,
+            AcuityAssetId::default(),
+            AcuityLockId::default()
+        ));
+        let lock_b = AcuityAtomicSwap::get_lock_id(A, B, hashed_secret, 3000);
+        assert_ok!(AcuityAtomicSwap::lock_sell(
+            RuntimeOrigin::signed(A),
+            B,
+            hashed_secret,
+            500,
+            5,
+            AcuityAssetId::default(),
+            AcuityLockId::default()
+        ));
+        let timed_out = AcuityAtomicSwap::get_lock_id(A, B, hashed_secret, 500);
+        // A lock for someone else.
+        assert_ok!(AcuityAtomicSwap::lock_sell(
+            RuntimeOrigin::signed(B),
+            A,
+            hashed_secret,
+            1000,
+            30,
+            AcuityAssetId::default(),
+            AcuityLockId::default()
+        ));
+        let other = AcuityAtomicSwap::get_lock_id(B, A, hashed_secret, 1000);
+        assert_eq!(
+            HashedSecretLocks::<Test>::iter_key_prefix(hashed_secret).count(),
+            4
+        );
+        Now::set(600);
+        assert_ok!(AcuityAtomicSwap::unlock_by_secret(
+            RuntimeOrigin::signed(B),
+            secret,
+            10
+        ));
+        assert_eq!(Balances::free_balance(B), 100);
+        assert_eq!(
+            AcuityAtomicSwap::lock_state(lock_a),
+            Some(LockState::Unlocked { secret })
+        );
+        assert_eq!(
+            AcuityAtomicSwap::lock_state(lock_b),
+            Some(LockState::Unlocked { secret })
+        );
+        assert_eq!(AcuityAtomicSwap::lock_id_value(timed_out), Some(5));
+        assert_eq!(AcuityAtomicSwap::lock_id_value(other), Some(30));
+        assert_eq!(
+            HashedSecretLocks::<Test>::iter_key_prefix(hashed_secret).count(),
+            2
+        );
+        assert!(HashedSecretLocks::<Test>::contains_key(
+            hashed_secret,
+            timed_out
+        ));
+        assert!(HashedSecretLocks::<Test>::contains_key(
+            hashed_secret,
+            other
+        ));
+        let unlock_events = System::events()
+            .into_iter()
+            .filter(|record| {
+                matches!(
+                    record.event,
+                    RuntimeEvent::AcuityAtomicSwap(Event::Unlock(..))
+                )
+            })
+            .count();
+        assert_eq!(unlock_events, 2);
+        assert_noop!(
+            AcuityAtomicSwap::unlock_by_secret(RuntimeOrigin::signed(B), secret, 10),
+            Error::<Test>::NoLocksForSecret
+        );
+    });
+}
+
+#[test]
+fn unlock_by_secret_fail_no_locks_for_secret() {
+    run_test(|| {
+        let secret = AcuitySecret::default();
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&secret.encode()));
+        assert_noop!(
+            AcuityAtomicSwap::unlock_by_secret(RuntimeOrigin::signed(B), secret, 10),
+            Error::<Test>::NoLocksForSecret
+        );
+        // The creator cannot claim their own lock.
+        assert_ok!(AcuityAtomicSwap::lock_sell(
+            RuntimeOrigin::signed(A),
+            B,
+            hashed_secret,
+            1000,
+            10,
+            AcuityAssetId::default(),
+            AcuityLockId::default()
+        ));
+        assert_noop!(
+            AcuityAtomicSwap::unlock_by_secret(RuntimeOrigin::signed(A), secret, 10),
+            Error::<Test>::NoLocksForSecret
+        );
+    });
+}
+
+#[test]
+fn unlock_by_secret_many_locks() {
+    run_test(|| {
+        let secret = AcuitySecret::default();
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&secret.encode()));
+        // Dust locks with the hashed secret do not stop other locks from being created.
+        for timeout in 1000..1020 {
+            assert_ok!(AcuityAtomicSwap::lock_sell(
+                RuntimeOrigin::signed(B),
+                A,
+                hashed_secret,
+                timeout,
+                1,
+                AcuityAssetId::default(),
+                AcuityLockId::default()
+            ));
+        }
+        assert_ok!(AcuityAtomicSwap::lock_sell(
+            RuntimeOrigin::signed(A),
+            B,
+            hashed_secret,
+            5000,
+            10,
+            AcuityAssetId::default(),
+            AcuityLockId::default()
+        ));
+        // Each call unlocks at most max locks, and locks for others do not take up the max.
+        assert_noop!(
+            AcuityAtomicSwap::unlock_by_secret(RuntimeOrigin::signed(B), secret, 0),
+            Error::<Test>::NoLocksForSecret
+        );
+        assert_ok!(AcuityAtomicSwap::unlock_by_secret(
+            RuntimeOrigin::signed(B),
+            secret,
+            1
+        ));
+        assert_eq!(Balances::free_balance(B), 90);
+        assert_ok!(AcuityAtomicSwap::unlock_by_secret(
+            RuntimeOrigin::signed(A),
+            secret,
+            21
+        ));
+        assert_eq!(Balances::free_balance(A), 110);
+    });
+}
+
+#[test]
+fn unlock_split() {
+    run_test(|| {
+        let secret = AcuitySecret::default();
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&secret.encode()));
+        let shares = vec![(B, 30), (C, 10)];
+        assert_ok!(AcuityAtomicSwap::lock_split(
+            RuntimeOrigin::signed(A),
+            hashed_secret,
+            1000,
+            shares.clone().try_into().unwrap(),
+            DeclineConsent::Any
+        ));
+        let lock_id = AcuityAtomicSwap::get_split_lock_id(&A, hashed_secret, 1000, &shares);
+        assert_eq!(Balances::free_balance(A), 60);
+        assert_eq!(AcuityAtomicSwap::lock_id_value(lock_id), Some(40));
+        assert_noop!(
+            AcuityAtomicSwap::unlock_split(RuntimeOrigin::signed(A), lock_id, secret),
+            Error::<Test>::NotShareRecipient
+        );
+        assert_noop!(
+            AcuityAtomicSwap::unlock_split(
+                RuntimeOrigin::signed(C),
+                lock_id,
+                AcuitySecret([1; 32])
+            ),
+            Error::<Test>::WrongSecret
+        );
+        assert_ok!(AcuityAtomicSwap::unlock_split(
+            RuntimeOrigin::signed(C),
+            lock_id,
+            secret
+        ));
+        assert_eq!(Balances::free_balance(B), 130);
+        assert_eq!(Balances::free_balance(C), 10);
+        assert_eq!(AcuityAtomicSwap::split_lock(lock_id), None);
+        assert_eq!(
+            AcuityAtomicSwap::lock_state(lock_id),
+            Some(LockState::Unlocked { secret })
+        );
+        assert_noop!(
+            AcuityAtomicSwap::unlock_split(RuntimeOrigin::signed(C), lock_id, secret),
+            Error::<Test>::LockAlreadyUnlocked
+        );
+    });
+}
+
+#[test]
+fn lock_split_fail_invalid_shares() {
+    run_test(|| {
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&AcuitySecret::default().encode()));
+        assert_noop!(
+            AcuityAtomicSwap::lock_split(
+                RuntimeOrigin::signed(A),
+                hashed_secret,
+                1000,
+                Default::default(),
+                DeclineConsent::Any
+            ),
+            Error::<Test>::NoShares
+        );
+        assert_noop!(
+            AcuityAtomicSwap::lock_split(
+                RuntimeOrigin::signed(A),
+                hashed_secret,
+                1000,
+                vec![(B, 10), (C, 0)].try_into().unwrap(),
+                DeclineConsent::Any
+            ),
+            Error::<Test>::ZeroValue
+        );
+        assert_noop!(
+            AcuityAtomicSwap::lock_split(
+                RuntimeOrigin::signed(A),
+                hashed_secret,
+                1000,
+                vec![(B, 10), (B, 10)].try_into().unwrap(),
+                DeclineConsent::Any
+            ),
+            Error::<Test>::DuplicateShareRecipient
+        );
+        assert_noop!(
+            AcuityAtomicSwap::lock_split(
+                RuntimeOrigin::signed(A),
+                hashed_secret,
+                1000,
+                vec![(B, 10), (C, 10)].try_into().unwrap(),
+                DeclineConsent::Threshold(3)
+            ),
+            Error::<Test>::InvalidDeclineConsent
+        );
+        ExistentialDeposit::set(10);
+        Balances::make_free_balance_be(&AcuityAtomicSwap::fund_account_id(), 10);
+        assert_noop!(
+            AcuityAtomicSwap::lock_split(
+                RuntimeOrigin::signed(A),
+                hashed_secret,
+                1000,
+                vec![(B, 10), (C, 5)].try_into().unwrap(),
+                DeclineConsent::Any
+            ),
+            Error::<Test>::ShareBelowExistentialDeposit
+        );
+        ExistentialDeposit::set(1);
+    });
+}
+
+#[test]
+fn decline_split_control_consent() {
+    run_test(|| {
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&AcuitySecret::default().encode()));
+        let shares = vec![(B, 30), (C, 10)];
+        // Any recipient can decline.
+        assert_ok!(AcuityAtomicSwap::lock_split(
+            RuntimeOrigin::signed(A),
+            hashed_secret,
+            1000,
+            shares.clone().try_into().unwrap(),
+            DeclineConsent::Any
+        ));
+        let lock_id = AcuityAtomicSwap::get_split_lock_id(&A, hashed_secret, 1000, &shares);
+        assert_ok!(AcuityAtomicSwap::decline_split(
+            RuntimeOrigin::signed(C),
+            lock_id
+        ));
+        assert_eq!(
+            AcuityAtomicSwap::lock_state(lock_id),
+            Some(LockState::Declined)
+        );
+        assert_eq!(Balances::free_balance(A), 100);
+
+        // Every recipient must decline.
+        assert_ok!(AcuityAtomicSwap::lock_split(
+            RuntimeOrigin::signed(A),
+            hashed_secret,
+            1001,
+            shares.clone().try_into().unwrap(),
+            DeclineConsent::All
+        ));
+        let lock_id = AcuityAtomicSwap::get_split_lock_id(&A, hashed_secret, 1001, &shares);
+        assert_ok!(AcuityAtomicSwap::decline_split(
+            RuntimeOrigin::signed(C),
+            lock_id
+        ));
+        assert_noop!(
+            AcuityAtomicSwap::decline_split(RuntimeOrigin::signed(C), lock_id),
+            Error::<Test>::AlreadyConsented
+        );
+        assert_noop!(
+            AcuityAtomicSwap::decline_split(RuntimeOrigin::signed(A), lock_id),
+            Error::<Test>::NotShareRecipient
+        );
+        assert_eq!(AcuityAtomicSwap::lock_id_value(lock_id), Some(40));
+        assert_ok!(AcuityAtomicSwap::decline_split(
+            RuntimeOrigin::signed(B),
+            lock_id
+        ));
+        assert_eq!(
+            AcuityAtomicSwap::lock_state(lock_id),
+            Some(LockState::Declined)
+        );
+        assert_eq!(Balances::free_balance(A), 100);
+    });
+}
+
+#[test]
+fn retrieve_split() {
+    run_test(|| {
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&AcuitySecret::default().encode()));
+        let shares = vec![(B, 30), (C, 10)];
+        assert_ok!(AcuityAtomicSwap::lock_split(
+            RuntimeOrigin::signed(A),
+            hashed_secret,
+            1000,
+            shares.clone().try_into().unwrap(),
+            DeclineConsent::Threshold(1)
+        ));
+        let lock_id = AcuityAtomicSwap::get_split_lock_id(&A, hashed_secret, 1000, &shares);
+        assert_noop!(
+            AcuityAtomicSwap::retrieve_split(RuntimeOrigin::signed(A), lock_id),
+            Error::<Test>::LockNotTimedOut
+        );
+        Now::set(1000);
+        assert_noop!(
+            AcuityAtomicSwap::retrieve_split(RuntimeOrigin::signed(B), lock_id),
+            Error::<Test>::LockDoesNotExist
+        );
+        assert_ok!(AcuityAtomicSwap::retrieve_split(
+            RuntimeOrigin::signed(A),
+            lock_id
+        ));
+        assert_eq!(Balances::free_balance(A), 100);
+        assert_eq!(
+            AcuityAtomicSwap::lock_state(lock_id),
+            Some(LockState::Retrieved)
+        );
+    });
+}
+
+#[test]
+fn reveal_secret() {
+    run_test(|| {
+        let secrets: Vec<(AcuitySecret, AcuityHashedSecret)> = (1..=3u8)
+            .map(|i| {
+                let secret = AcuitySecret([i; 32]);
+                let mut hashed_secret = AcuityHashedSecret::default();
+                hashed_secret
+                    .0
+                    .copy_from_slice(&keccak_256(&secret.encode()));
+                (secret, hashed_secret)
+            })
+            .collect();
+        let hashed_secrets: Vec<AcuityHashedSecret> = secrets.iter().map(|(_, h)| *h).collect();
+        assert_ok!(AcuityAtomicSwap::lock_threshold(
+            RuntimeOrigin::signed(A),
+            B,
+            hashed_secrets.clone().try_into().unwrap(),
+            2,
+            1000,
+            50
+        ));
+        let lock_id = AcuityAtomicSwap::get_threshold_lock_id(&A, &B, &hashed_secrets, 2, 1000);
+        assert_eq!(Balances::free_balance(A), 50);
+        assert_noop!(
+            AcuityAtomicSwap::reveal_secret(
+                RuntimeOrigin::signed(C),
+                lock_id,
+                AcuitySecret::default()
+            ),
+            Error::<Test>::WrongSecret
+        );
+        // Anyone can reveal a secret.
+        assert_ok!(AcuityAtomicSwap::reveal_secret(
+            RuntimeOrigin::signed(C),
+            lock_id,
+            secrets[2].0
+        ));
+        assert_eq!(
+            AcuityAtomicSwap::threshold_lock_progress(lock_id),
+            Some(ThresholdLockProgress {
+                secrets: vec![
+                    (secrets[0].1, None),
+                    (secrets[1].1, None),
+                    (secrets[2].1, Some(secrets[2].0)),
+                ],
+                threshold: 2,
+            })
+        );
+        assert_noop!(
+            AcuityAtomicSwap::reveal_secret(RuntimeOrigin::signed(C), lock_id, secrets[2].0),
+            Error::<Test>::SecretAlreadyRevealed
+        );
+        assert_eq!(Balances::free_balance(B), 100);
+        assert_ok!(AcuityAtomicSwap::reveal_secret(
+            RuntimeOrigin::signed(B),
+            lock_id,
+            secrets[0].0
+        ));
+        assert_eq!(Balances::free_balance(B), 150);
+        assert_eq!(AcuityAtomicSwap::threshold_lock_progress(lock_id), None);
+        assert_eq!(
+            AcuityAtomicSwap::lock_state(lock_id),
+            Some(LockState::Unlocked {
+                secret: secrets[0].0
+            })
+        );
+        assert_noop!(
+            AcuityAtomicSwap::reveal_secret(RuntimeOrigin::signed(B), lock_id, secrets[1].0),
+            Error::<Test>::LockAlreadyUnlocked
+        );
+    });
+}
+
+#[test]
+fn reveal_secret_fail_lock_timed_out() {
+    run_test(|| {
+        let secrets: Vec<(AcuitySecret, AcuityHashedSecret)> = (1..=3u8)
+            .map(|i| {
+                let secret = AcuitySecret([i; 32]);
+                let mut hashed_secret = AcuityHashedSecret::default();
+                hashed_secret
+                    .0
+                    .copy_from_slice(&keccak_256(&secret.encode()));
+                (secret, hashed_secret)
+            })
+            .collect();
+        let hashed_secrets: Vec<AcuityHashedSecret> = secrets.iter().map(|(_, h)| *h).collect();
+        assert_ok!(AcuityAtomicSwap::lock_threshold(
+            RuntimeOrigin::signed(A),
+            B,
+            hashed_secrets.clone().try_into().unwrap(),
+            3,
+            1000,
+            50
+        ));
+        let lock_id = AcuityAtomicSwap::get_threshold_lock_id(&A, &B, &hashed_secrets, 3, 1000);
+        Now::set(1000);
+        assert_noop!(
+            AcuityAtomicSwap::reveal_secret(RuntimeOrigin::signed(B), lock_id, secrets[0].0),
+            Error::<Test>::LockTimedOut
+        );
+        assert_ok!(AcuityAtomicSwap::retrieve_threshold(
+            RuntimeOrigin::signed(A),
+            lock_id
+        ));
+        assert_eq!(Balances::free_balance(A), 100);
+    });
+}
+
+#[test]
+fn lock_threshold_fail_invalid() {
+    run_test(|| {
+        let secrets: Vec<(AcuitySecret, AcuityHashedSecret)> = (1..=3u8)
+            .map(|i| {
+                let secret = AcuitySecret([i; 32]);
+                let mut hashed_secret = AcuityHashedSecret::default();
+                hashed_secret
+                    .0
+                    .copy_from_slice(&keccak_256(&secret.encode()));
+                (secret, hashed_secret)
+            })
+            .collect();
+        assert_noop!(
+            AcuityAtomicSwap::lock_threshold(
+                RuntimeOrigin::signed(A),
+                B,
+                Default::default(),
+                1,
+                1000,
+                50
+            ),
+            Error::<Test>::NoHashedSecrets
+        );
+        assert_noop!(
+            AcuityAtomicSwap::lock_threshold(
+                RuntimeOrigin::signed(A),
+                B,
+                vec![secrets[0].1, secrets[0].1].try_into().unwrap(),
+                1,
+                1000,
+                50
+            ),
+            Error::<Test>::DuplicateHashedSecret
+        );
+        assert_noop!(
+            AcuityAtomicSwap::lock_threshold(
+                RuntimeOrigin::signed(A),
+                B,
+                vec![secrets[0].1, secrets[1].1].try_into().unwrap(),
+                3,
+                1000,
+                50
+            ),
+            Error::<Test>::InvalidThreshold
+        );
+        assert_noop!(
+            AcuityAtomicSwap::lock_threshold(
+                RuntimeOrigin::signed(A),
+                B,
+                vec![secrets[0].1].try_into().unwrap(),
+                0,
+                1000,
+                50
+            ),
+            Error::<Test>::InvalidThreshold
+        );
+    });
+}
+
+#[test]
+fn decline_threshold() {
+    run_test(|| {
+        let secrets: Vec<(AcuitySecret, AcuityHashedSecret)> = (1..=3u8)
+            .map(|i| {
+                let secret = AcuitySecret([i; 32]);
+                let mut hashed_secret = AcuityHashedSecret::default();
+                hashed_secret
+                    .0
+                    .copy_from_slice(&keccak_256(&secret.encode()));
+                (secret, hashed_secret)
+            })
+            .collect();
+        let hashed_secrets: Vec<AcuityHashedSecret> = secrets.iter().map(|(_, h)| *h).collect();
+        assert_ok!(AcuityAtomicSwap::lock_threshold(
+            RuntimeOrigin::signed(A),
+            B,
+            hashed_secrets.clone().try_into().unwrap(),
+            1,
+            1000,
+            50
+        ));
+        let lock_id = AcuityAtomicSwap::get_threshold_lock_id(&A, &B, &hashed_secrets, 1, 1000);
+        assert_noop!(
+            AcuityAtomicSwap::decline_threshold(RuntimeOrigin::signed(A), lock_id),
+            Error::<Test>::LockDoesNotExist
+        );
+        assert_ok!(AcuityAtomicSwap::decline_threshold(
+            RuntimeOrigin::signed(B),
+            lock_id
+        ));
+        assert_eq!(Balances::free_balance(A), 100);
+        assert_eq!(
+            AcuityAtomicSwap::lock_state(lock_id),
+            Some(LockState::Declined)
+        );
+    });
+}
+
+// Generated offline: the scalar 0x0102..20 and its compressed secp256k1 point.
+const PTLC_SCALAR: [u8; 32] = [
+    0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f, 0x10,
+    0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d, 0x1e, 0x1f, 0x20,
+];
+
+const PTLC_POINT: [u8; 33] = [
+    0x02, 0x84, 0xbf, 0x75, 0x62, 0x26, 0x2b, 0xbd, 0x69, 0x40, 0x08, 0x57, 0x48, 0xf3, 0xbe, 0x6a,
+    0xfa, 0x52, 0xae, 0x31, 0x71, 0x55, 0x18, 0x1e, 0xce, 0x31, 0xb6, 0x63, 0x51, 0xcc, 0xff, 0xa4,
+    0xb0,
+];
+
+#[test]
+fn secp256k1_point_from_scalar() {
+    run_test(|| {
+        assert_eq!(secp256k1::point_from_scalar(&PTLC_SCALAR), Some(PTLC_POINT));
+        // One is the generator.
+        let mut one = [0u8; 32];
+        one[31] = 1;
+        let mut generator = [0u8; 33];
+        generator[0] = 0x02;
+        generator[1..].copy_from_slice(&[
+            0x79, 0xbe, 0x66, 0x7e, 0xf9, 0xdc, 0xbb, 0xac, 0x55, 0xa0, 0x62, 0x95, 0xce, 0x87,
+            0x0b, 0x07, 0x02, 0x9b, 0xfc, 0xdb, 0x2d, 0xce, 0x28, 0xd9, 0x59, 0xf2, 0x81, 0x5b,
+            0x16, 0xf8, 0x17, 0x98,
+        ]);
+        assert_eq!(secp256k1::point_from_scalar(&one), Some(generator));
+        // The group order minus one is the negated generator.
+        let order_minus_one = [
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+            0xff, 0xfe, 0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c,
+            0xd0, 0x36, 0x41, 0x40,
+        ];
+        generator[0] = 0x03;
+        assert_eq!(
+            secp256k1::point_from_scalar(&order_minus_one),
+            Some(generator)
+        );
+        // Zero and the group order are not valid scalars.
+        assert_eq!(secp256k1::point_from_scalar(&[0u8; 32]), None);
+        let mut order = order_minus_one;
+        order[31] = 0x41;
+        assert_eq!(secp256k1::point_from_scalar(&order), None);
+    });
+}
+
+#[test]
+fn unlock_ptlc() {
+    run_test(|| {
+        let point = AcuityPoint(PTLC_POINT);
+        let scalar = AcuitySecret(PTLC_SCALAR);
+        assert_ok!(AcuityAtomicSwap::lock_ptlc(
+            RuntimeOrigin::signed(A),
+            B,
+            point,
+            1000,
+            50
+        ));
+        let lock_id = AcuityAtomicSwap::get_ptlc_lock_id(&A, &B, point, 1000);
+        assert_eq!(AcuityAtomicSwap::lock_id_value(lock_id), Some(50));
+        assert_noop!(
+            AcuityAtomicSwap::unlock_ptlc(
+                RuntimeOrigin::signed(B),
+                A,
+                AcuitySecret::default(),
+                1000
+            ),
+            Error::<Test>::InvalidScalar
+        );
+        let mut wrong_scalar = PTLC_SCALAR;
+        wrong_scalar[0] = 0x02;
+        assert_noop!(
+            AcuityAtomicSwap::unlock_ptlc(
+                RuntimeOrigin::signed(B),
+                A,
+                AcuitySecret(wrong_scalar),
+                1000
+            ),
+            Error::<Test>::LockDoesNotExist
+        );
+        assert_ok!(AcuityAtomicSwap::unlock_ptlc(
+            RuntimeOrigin::signed(B),
+            A,
+            scalar,
+            1000
+        ));
+        assert_eq!(Balances::free_balance(B), 150);
+        assert_eq!(
+            AcuityAtomicSwap::lock_state(lock_id),
+            Some(LockState::Unlocked { secret: scalar })
+        );
+    });
+}
+
+#[test]
+fn decline_ptlc() {
+    run_test(|| {
+        let point = AcuityPoint(PTLC_POINT);
+        assert_ok!(AcuityAtomicSwap::lock_ptlc(
+            RuntimeOrigin::signed(A),
+            B,
+            point,
+            1000,
+            50
+        ));
+        assert_ok!(AcuityAtomicSwap::decline_ptlc(
+            RuntimeOrigin::signed(B),
+            A,
+            point,
+            1000
+        ));
+        assert_eq!(Balances::free_balance(A), 100);
+        assert_noop!(
+            AcuityAtomicSwap::unlock_ptlc(
+                RuntimeOrigin::signed(B),
+                A,
+                AcuitySecret(PTLC_SCALAR),
+                1000
+            ),
+            Error::<Test>::LockAlreadyDeclined
+        );
+    });
+}
+
+#[test]
+fn retrieve_ptlc() {
+    run_test(|| {
+        let point = AcuityPoint(PTLC_POINT);
+        assert_ok!(AcuityAtomicSwap::lock_ptlc(
+            RuntimeOrigin::signed(A),
+            B,
+            point,
+            1000,
+            50
+        ));
+        assert_noop!(
+            AcuityAtomicSwap::retrieve_ptlc(RuntimeOrigin::signed(A), B, point, 1000),
+            Error::<Test>::LockNotTimedOut
+        );
+        Now::set(1000);
+        assert_noop!(
+            AcuityAtomicSwap::unlock_ptlc(
+                RuntimeOrigin::signed(B),
+                A,
+                AcuitySecret(PTLC_SCALAR),
+                1000
+            ),
+            Error::<Test>::LockTimedOut
+        );
+        assert_ok!(AcuityAtomicSwap::retrieve_ptlc(
+            RuntimeOrigin::signed(A),
+            B,
+            point,
+            1000
+        ));
+        assert_eq!(Balances::free_balance(A), 100);
+    });
+}
+
+#[test]
+fn top_up() {
+    run_test(|| {
+        System::set_block_number(1);
+        let secret = AcuitySecret::default();
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&secret.encode()));
+        assert_ok!(AcuityAtomicSwap::lock_sell(
+            RuntimeOrigin::signed(A),
+            B,
+            hashed_secret,
+            1000,
+            10,
+            AcuityAssetId::default(),
+            AcuityLockId::default()
+        ));
+        let lock_id = AcuityAtomicSwap::get_lock_id(A, B, hashed_secret, 1000);
+        assert_ok!(AcuityAtomicSwap::top_up(
+            RuntimeOrigin::signed(A),
+            B,
+            hashed_secret,
+            1000,
+            15
+        ));
+        assert_eq!(AcuityAtomicSwap::lock_id_value(lock_id), Some(25));
+        assert_eq!(Balances::free_balance(A), 75);
+        assert_eq!(
+            Balances::free_balance(AcuityAtomicSwap::fund_account_id()),
+            26
+        );
+        System::assert_last_event(RuntimeEvent::AcuityAtomicSwap(Event::LockToppedUp(
+            A, B, lock_id, 15, 25,
+        )));
+        assert_ok!(AcuityAtomicSwap::unlock(
+            RuntimeOrigin::signed(B),
+            A,
+            secret,
+            1000
+        ));
+        assert_eq!(Balances::free_balance(B), 125);
+    });
+}
+
+#[test]
+fn top_up_fail() {
+    run_test(|| {
+        let secret = AcuitySecret::default();
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&secret.encode()));
+        assert_noop!(
+            AcuityAtomicSwap::top_up(RuntimeOrigin::signed(A), B, hashed_secret, 1000, 15),
+            Error::<Test>::LockDoesNotExist
+        );
+        assert_ok!(AcuityAtomicSwap::lock_sell(
+            RuntimeOrigin::signed(A),
+            B,
+            hashed_secret,
+            1000,
+            10,
+            AcuityAssetId::default(),
+            AcuityLockId::default()
+        ));
+        assert_noop!(
+            AcuityAtomicSwap::top_up(RuntimeOrigin::signed(A), B, hashed_secret, 1000, 0),
+            Error::<Test>::ZeroValue
+        );
+        // Only the creator can top up.
+        assert_noop!(
+            AcuityAtomicSwap::top_up(RuntimeOrigin::signed(B), A, hashed_secret, 1000, 15),
+            Error::<Test>::LockDoesNotExist
+        );
+        assert_noop!(
+            AcuityAtomicSwap::top_up(RuntimeOrigin::signed(A), B, hashed_secret, 1000, 91),
+            Error::<Test>::InsufficientBalance
+        );
+        assert_ok!(AcuityAtomicSwap::unlock(
+            RuntimeOrigin::signed(B),
+            A,
+            secret,
+            1000
+        ));
+        assert_noop!(
+            AcuityAtomicSwap::top_up(RuntimeOrigin::signed(A), B, hashed_secret, 1000, 15),
+            Error::<Test>::LockAlreadyUnlocked
+        );
+        Now::set(1000);
+        assert_ok!(AcuityAtomicSwap::lock_sell(
+            RuntimeOrigin::signed(A),
+            B,
+            hashed_secret,
+            1001,
+            10,
+            AcuityAssetId::default(),
+            AcuityLockId::default()
+        ));
+        Now::set(1001);
+        assert_noop!(
+            AcuityAtomicSwap::top_up(RuntimeOrigin::signed(A), B, hashed_secret, 1001, 15),
+            Error::<Test>::LockTimedOut
+        );
+    });
+}
+
+#[test]
+fn top_up_fail_value_outside_swap_size() {
+    run_test(|| {
+        let now = Now::get();
+        assert_ok!(AcuityAtomicSwap::register_liquidity_provider(
+            RuntimeOrigin::signed(A),
+            AcuityAssetId::default(),
+            AcuityForeignAddress::default(),
+            10,
+            40
+        ));
+        assert_ok!(AcuityAtomicSwap::lock_buy(
+            RuntimeOrigin::signed(B),
+            A,
+            AcuityHashedSecret::default(),
+            now + 1000,
+            30,
+            AcuityAssetId::default(),
+            5
+        ));
+        let lock_id =
+            AcuityAtomicSwap::get_lock_id(B, A, AcuityHashedSecret::default(), now + 1000);
+        // The lock cannot grow beyond the recipient's maximum swap size.
+        assert_noop!(
+            AcuityAtomicSwap::top_up(
+                RuntimeOrigin::signed(B),
+                A,
+                AcuityHashedSecret::default(),
+                now + 1000,
+                11
+            ),
+            Error::<Test>::ValueOutsideSwapSize
+        );
+        assert_ok!(AcuityAtomicSwap::top_up(
+            RuntimeOrigin::signed(B),
+            A,
+            AcuityHashedSecret::default(),
+            now + 1000,
+            10
+        ));
+        assert_eq!(AcuityAtomicSwap::lock_id_value(lock_id), Some(40));
+    });
+}
+
+#[test]
+fn mint_claim() {
+    run_test(|| {
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&AcuitySecret::default().encode()));
+        assert_noop!(
+            AcuityAtomicSwap::mint_claim(RuntimeOrigin::signed(B), A, hashed_secret, 1000),
+            Error::<Test>::LockDoesNotExist
+        );
+        assert_ok!(AcuityAtomicSwap::lock_sell(
+            RuntimeOrigin::signed(A),
+            B,
+            hashed_secret,
+            1000,
+            50,
+            AcuityAssetId::default(),
+            AcuityLockId::default()
+        ));
+        let lock_id = AcuityAtomicSwap::get_lock_id(A, B, hashed_secret, 1000);
+        assert_ok!(AcuityAtomicSwap::mint_claim(
+            RuntimeOrigin::signed(B),
+            A,
+            hashed_secret,
+            1000
+        ));
+        let claim_id = AcuityAtomicSwap::lock_claim(lock_id).unwrap();
+        assert_eq!(Uniques::owner(CLAIM_COLLECTION, claim_id), Some(B));
+        assert_noop!(
+            AcuityAtomicSwap::mint_claim(RuntimeOrigin::signed(B), A, hashed_secret, 1000),
+            Error::<Test>::ClaimAlreadyMinted
+        );
+    });
+}
+
+#[test]
+fn mint_claim_fail_no_nfts() {
+    run_test(|| {
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&AcuitySecret::default().encode()));
+        // The stable instance has no NFT pallet.
+        assert_ok!(AcuityAtomicSwapStable::lock_sell(
+            RuntimeOrigin::signed(A),
+            B,
+            hashed_secret,
+            1000,
+            50,
+            AcuityAssetId::default(),
+            AcuityLockId::default()
+        ));
+        assert_noop!(
+            AcuityAtomicSwapStable::mint_claim(RuntimeOrigin::signed(B), A, hashed_secret, 1000),
+            TokenError::Unsupported
+        );
+        let lock_id = AcuityAtomicSwapStable::get_lock_id(A, B, hashed_secret, 1000);
+        assert_eq!(AcuityAtomicSwapStable::lock_claim(lock_id), None);
+    });
+}
+
+#[test]
+fn unlock_control_claim_sold() {
+    run_test(|| {
+        let secret = AcuitySecret::default();
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&secret.encode()));
+        assert_ok!(AcuityAtomicSwap::lock_sell(
+            RuntimeOrigin::signed(A),
+            B,
+            hashed_secret,
+            1000,
+            50,
+            AcuityAssetId::default(),
+            AcuityLockId::default()
+        ));
+        let lock_id = AcuityAtomicSwap::get_lock_id(A, B, hashed_secret, 1000);
+        assert_ok!(AcuityAtomicSwap::mint_claim(
+            RuntimeOrigin::signed(B),
+            A,
+            hashed_secret,
+            1000
+        ));
+        let claim_id = AcuityAtomicSwap::lock_claim(lock_id).unwrap();
+        assert_ok!(Uniques::transfer(
+            RuntimeOrigin::signed(B),
+            CLAIM_COLLECTION,
+            claim_id,
+            C
+        ));
+        // The seller of the claim can no longer decline.
+        assert_noop!(
+            AcuityAtomicSwap::decline(RuntimeOrigin::signed(B), A, hashed_secret, 1000),
+            Error::<Test>::NotClaimOwner
+        );
+        // Unlocking pays the owner of the claim.
+        assert_ok!(AcuityAtomicSwap::unlock(
+            RuntimeOrigin::signed(B),
+            A,
+            secret,
+            1000
+        ));
+        assert_eq!(Balances::free_balance(B), 100);
+        assert_eq!(Balances::free_balance(C), 50);
+        assert_eq!(Uniques::owner(CLAIM_COLLECTION, claim_id), None);
+        assert_eq!(AcuityAtomicSwap::lock_claim(lock_id), None);
+    });
+}
+
+#[test]
+fn unlock_claim() {
+    run_test(|| {
+        let secret = AcuitySecret::default();
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&secret.encode()));
+        assert_noop!(
+            AcuityAtomicSwap::unlock_claim(RuntimeOrigin::signed(C), A, B, secret, 1000),
+            Error::<Test>::LockDoesNotExist
+        );
+        assert_ok!(AcuityAtomicSwap::lock_sell(
+            RuntimeOrigin::signed(A),
+            B,
+            hashed_secret,
+            1000,
+            49,
+            AcuityAssetId::default(),
+            AcuityLockId::default()
+        ));
+        assert_noop!(
+            AcuityAtomicSwap::unlock_claim(RuntimeOrigin::signed(C), A, B, secret, 1000),
+            Error::<Test>::ClaimDoesNotExist
+        );
+        assert_ok!(AcuityAtomicSwap::lock_sell(
+            RuntimeOrigin::signed(A),
+            B,
+            hashed_secret,
+            1001,
+            50,
+            AcuityAssetId::default(),
+            AcuityLockId::default()
+        ));
+        let lock_id = AcuityAtomicSwap::get_lock_id(A, B, hashed_secret, 1001);
+        assert_ok!(AcuityAtomicSwap::mint_claim(
+            RuntimeOrigin::signed(B),
+            A,
+            hashed_secret,
+            1001
+        ));
+        let claim_id = AcuityAtomicSwap::lock_claim(lock_id).unwrap();
+        assert_ok!(Uniques::transfer(
+            RuntimeOrigin::signed(B),
+            CLAIM_COLLECTION,
+            claim_id,
+            C
+        ));
+        assert_ok!(AcuityAtomicSwap::unlock_claim(
+            RuntimeOrigin::signed(C),
+            A,
+            B,
+            secret,
+            1001
+        ));
+        assert_eq!(Balances::free_balance(C), 50);
+        assert_eq!(Uniques::owner(CLAIM_COLLECTION, claim_id), None);
+    });
+}
+
+#[test]
+fn decline_control_claim() {
+    run_test(|| {
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&AcuitySecret::default().encode()));
+        assert_ok!(AcuityAtomicSwap::lock_sell(
+            RuntimeOrigin::signed(A),
+            B,
+            hashed_secret,
+            1000,
+            50,
+            AcuityAssetId::default(),
+            AcuityLockId::default()
+        ));
+        let lock_id = AcuityAtomicSwap::get_lock_id(A, B, hashed_secret, 1000);
+        assert_ok!(AcuityAtomicSwap::mint_claim(
+            RuntimeOrigin::signed(B),
+            A,
+            hashed_secret,
+            1000
+        ));
+        let claim_id = AcuityAtomicSwap::lock_claim(lock_id).unwrap();
+        assert_ok!(AcuityAtomicSwap::decline(
+            RuntimeOrigin::signed(B),
+            A,
+            hashed_secret,
+            1000
+        ));
+        assert_eq!(Balances::free_balance(A), 100);
+        assert_eq!(Uniques::owner(CLAIM_COLLECTION, claim_id), None);
+        assert_eq!(AcuityAtomicSwap::lock_claim(lock_id), None);
+    });
+}
+
+#[test]
+fn retrieve_control_claim() {
+    run_test(|| {
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&AcuitySecret::default().encode()));
+        assert_ok!(AcuityAtomicSwap::lock_sell(
+            RuntimeOrigin::signed(A),
+            B,
+            hashed_secret,
+            1000,
+            50,
+            AcuityAssetId::default(),
+            AcuityLockId::default()
+        ));
+        let lock_id = AcuityAtomicSwap::get_lock_id(A, B, hashed_secret, 1000);
+        assert_ok!(AcuityAtomicSwap::mint_claim(
+            RuntimeOrigin::signed(B),
+            A,
+            hashed_secret,
+            1000
+        ));
+        let claim_id = AcuityAtomicSwap::lock_claim(lock_id).unwrap();
+        assert_ok!(Uniques::transfer(
+            RuntimeOrigin::signed(B),
+            CLAIM_COLLECTION,
+            claim_id,
+            C
+        ));
+        Now::set(1000);
+        assert_ok!(AcuityAtomicSwap::retrieve(
+            RuntimeOrigin::signed(A),
+            B,
+            hashed_secret,
+            1000
+        ));
+        assert_eq!(Balances::free_balance(A), 100);
+        assert_eq!(Uniques::owner(CLAIM_COLLECTION, claim_id), None);
+    });
+}
+
+const NFT_COLLECTION: u32 = 2;
+const NFT_ITEM: u64 = 7;
+
+#[test]
+fn lock_nft() {
+    run_test(|| {
+        System::set_block_number(1);
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&AcuitySecret::default().encode()));
+        assert_ok!(Uniques::force_create(
+            RuntimeOrigin::root(),
+            NFT_COLLECTION,
+            A,
+            true
+        ));
+        assert_ok!(Uniques::mint(
+            RuntimeOrigin::signed(A),
+            NFT_COLLECTION,
+            NFT_ITEM,
+            A
+        ));
+        assert_ok!(AcuityAtomicSwap::lock_nft(
+            RuntimeOrigin::signed(A),
+            NFT_COLLECTION,
+            NFT_ITEM,
+            B,
+            hashed_secret,
+            1000
+        ));
+        let lock_id = AcuityAtomicSwap::get_nft_lock_id(&A, &B, hashed_secret, 1000);
+        System::assert_last_event(RuntimeEvent::AcuityAtomicSwap(Event::NftLock(
+            A,
+            B,
+            hashed_secret,
+            1000,
+            NFT_COLLECTION,
+            NFT_ITEM,
+            lock_id,
+        )));
+        assert_eq!(
+            Uniques::owner(NFT_COLLECTION, NFT_ITEM),
+            Some(AcuityAtomicSwap::fund_account_id())
+        );
+        assert_eq!(
+            AcuityAtomicSwap::nft_lock(lock_id),
+            Some((NFT_COLLECTION, NFT_ITEM))
+        );
+        assert_eq!(AcuityAtomicSwap::lock_state(lock_id), Some(LockState::Open));
+    });
+}
+
+#[test]
+fn lock_nft_fail() {
+    run_test(|| {
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&AcuitySecret::default().encode()));
+        assert_ok!(Uniques::force_create(
+            RuntimeOrigin::root(),
+            NFT_COLLECTION,
+            A,
+            true
+        ));
+        assert_ok!(Uniques::mint(
+            RuntimeOrigin::signed(A),
+            NFT_COLLECTION,
+            NFT_ITEM,
+            A
+        ));
+        assert_ok!(AcuityAtomicSwap::lock_nft(
+            RuntimeOrigin::signed(A),
+            NFT_COLLECTION,
+            NFT_ITEM,
+            B,
+            hashed_secret,
+            1000
+        ));
+        assert_noop!(
+            AcuityAtomicSwap::lock_nft(
+                RuntimeOrigin::signed(A),
+                NFT_COLLECTION,
+                NFT_ITEM,
+                B,
+                hashed_secret,
+                1000
+            ),
+            Error::<Test>::LockAlreadyExists
+        );
+        assert_noop!(
+            AcuityAtomicSwap::lock_nft(
+                RuntimeOrigin::signed(A),
+                NFT_COLLECTION,
+                NFT_ITEM,
+                B,
+                hashed_secret,
+                1001
+            ),
+            Error::<Test>::NotNftOwner
+        );
+        assert_noop!(
+            AcuityAtomicSwap::lock_nft(
+                RuntimeOrigin::signed(B),
+                NFT_COLLECTION,
+                NFT_ITEM + 1,
+                A,
+                hashed_secret,
+                1000
+            ),
+            Error::<Test>::NotNftOwner
+        );
+    });
+}
+
+#[test]
+fn lock_nft_fail_claim_nft() {
+    run_test(|| {
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&AcuitySecret::default().encode()));
+        assert_ok!(AcuityAtomicSwap::lock_sell(
+            RuntimeOrigin::signed(A),
+            B,
+            hashed_secret,
+            1000,
+            50,
+            AcuityAssetId::default(),
+            AcuityLockId::default()
+        ));
+        let lock_id = AcuityAtomicSwap::get_lock_id(A, B, hashed_secret, 1000);
+        assert_ok!(AcuityAtomicSwap::mint_claim(
+            RuntimeOrigin::signed(B),
+            A,
+            hashed_secret,
+            1000
+        ));
+        let claim_id = AcuityAtomicSwap::lock_claim(lock_id).unwrap();
+        // A claim cannot be escrowed, or settling its lock would burn it while it is locked.
+        assert_noop!(
+            AcuityAtomicSwap::lock_nft(
+                RuntimeOrigin::signed(B),
+                CLAIM_COLLECTION,
+                claim_id,
+                A,
+                hashed_secret,
+                1000
+            ),
+            Error::<Test>::ClaimNftNotLockable
+        );
+    });
+}
+
+#[test]
+fn lock_nft_fail_no_nfts() {
+    run_test(|| {
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&AcuitySecret::default().encode()));
+        // The stable instance has no NFT pallet.
+        assert_noop!(
+            AcuityAtomicSwapStable::lock_nft(
+                RuntimeOrigin::signed(A),
+                1,
+                7,
+                B,
+                hashed_secret,
+                1000
+            ),
+            Error::<Test, Instance2>::NotNftOwner
+        );
+    });
+}
+
+#[test]
+fn unlock_nft() {
+    run_test(|| {
+        System::set_block_number(1);
+        let secret = AcuitySecret::default();
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&secret.encode()));
+        assert_ok!(Uniques::force_create(
+            RuntimeOrigin::root(),
+            NFT_COLLECTION,
+            A,
+            true
+        ));
+        assert_ok!(Uniques::mint(
+            RuntimeOrigin::signed(A),
+            NFT_COLLECTION,
+            NFT_ITEM,
+            A
+        ));
+        assert_ok!(AcuityAtomicSwap::lock_nft(
+            RuntimeOrigin::signed(A),
+            NFT_COLLECTION,
+            NFT_ITEM,
+            B,
+            hashed_secret,
+            1000
+        ));
+        let lock_id = AcuityAtomicSwap::get_nft_lock_id(&A, &B, hashed_secret, 1000);
+        assert_ok!(AcuityAtomicSwap::unlock_nft(
+            RuntimeOrigin::signed(B),
+            A,
+            secret,
+            1000
+        ));
+        System::assert_last_event(RuntimeEvent::AcuityAtomicSwap(Event::NftUnlock(
+            A,
+            B,
+            lock_id,
+            secret,
+            NFT_COLLECTION,
+            NFT_ITEM,
+        )));
+        assert_eq!(Uniques::owner(NFT_COLLECTION, NFT_ITEM), Some(B));
+        assert_eq!(AcuityAtomicSwap::nft_lock(lock_id), None);
+        assert_noop!(
+            AcuityAtomicSwap::unlock_nft(RuntimeOrigin::signed(B), A, secret, 1000),
+            Error::<Test>::LockAlreadyUnlocked
+        );
+    });
+}
+
+#[test]
+fn decline_nft() {
+    run_test(|| {
+        System::set_block_number(1);
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&AcuitySecret::default().encode()));
+        assert_ok!(Uniques::force_create(
+            RuntimeOrigin::root(),
+            NFT_COLLECTION,
+            A,
+            true
+        ));
+        assert_ok!(Uniques::mint(
+            RuntimeOrigin::signed(A),
+            NFT_COLLECTION,
+            NFT_ITEM,
+            A
+        ));
+        assert_ok!(AcuityAtomicSwap::lock_nft(
+            RuntimeOrigin::signed(A),
+            NFT_COLLECTION,
+            NFT_ITEM,
+            B,
+            hashed_secret,
+            1000
+        ));
+        let lock_id = AcuityAtomicSwap::get_nft_lock_id(&A, &B, hashed_secret, 1000);
+        assert_ok!(AcuityAtomicSwap::decline_nft(
+            RuntimeOrigin::signed(B),
+            A,
+            hashed_secret,
+            1000
+        ));
+        System::assert_last_event(RuntimeEvent::AcuityAtomicSwap(Event::NftDecline(
+            A,
+            B,
+            lock_id,
+            NFT_COLLECTION,
+            NFT_ITEM,
+        )));
+        assert_eq!(Uniques::owner(NFT_COLLECTION, NFT_ITEM), Some(A));
+        assert_noop!(
+            AcuityAtomicSwap::decline_nft(RuntimeOrigin::signed(B), A, hashed_secret, 1000),
+            Error::<Test>::LockAlreadyDeclined
+        );
+    });
+}
+
+#[test]
+fn retrieve_nft() {
+    run_test(|| {
+        System::set_block_number(1);
+        let secret = AcuitySecret::default();
+        let mut hashed_secret = AcuityHashedSecret::default();
+        hashed_secret
+            .0
+            .copy_from_slice(&keccak_256(&secret.encode()));
+        assert_ok!(Uniques::force_create(
+            RuntimeOrigin::root(),
+            NFT_COLLECTION,
+            A,
+            true
+        ));
+        assert_ok!(Uniques::mint(
+            RuntimeOrigin::signed(A),
+            NFT_COLLECTION,
+            NFT_ITEM,
+            A
+        ));
+        assert_ok!(AcuityAtomicSwap::lock_nft(
+            RuntimeOrigin::signed(A),
+            NFT_COLLECTION,
+            NFT_ITEM,
+            B,
+            hashed_secret,
+            1000
+        ));
+        let lock_id = AcuityAtomicSwap::get_nft_lock_id(&A, &B, hashed_secret, 1000);
+        assert_noop!(
+            AcuityAtomicSwap::retrieve_nft(RuntimeOrigin::signed(A), B, hashed_secret, 1000),
+            Error::<Test>::LockNotTimedOut
+        );
+        Now::set(1000);
+        assert_noop!(
+            AcuityAtomicSwap::unlock_nft(RuntimeOrigin::signed(B), A, secret, 1000),
+            Error::<Test>::LockTimedOut
+        );
+        assert_ok!(AcuityAtomicSwap::retrieve_nft(
+            RuntimeOrigin::signed(A),
+            B,
+            hashed_secret,
+            1000
+        ));
+        System::assert_last_event(RuntimeEvent::AcuityAtomicSwap(Event::NftRetrieve(
+            A,
+            B,
+            lock_id,
+            NFT_COLLECTION,
+            NFT_ITEM,
+        )));
+        assert_eq!(Uniques::owner(NFT_COLLECTION, NFT_ITEM), Some(A));
+    });
+}